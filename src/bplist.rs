@@ -0,0 +1,202 @@
+// Just enough of Apple's binary property list format to read the
+// NSKeyedArchiver blobs that Terminal.app stores colors in.
+// https://opensource.apple.com/source/CF/CF-550/CFBinaryPList.c
+
+use std::cell::Cell;
+use std::str;
+
+const MAX_DEPTH: usize = 32;
+
+#[derive(Debug, PartialEq)]
+pub enum Value {
+    Null,
+    Bool(bool),
+    Integer(i64),
+    Real(f64),
+    Data(Vec<u8>),
+    String(String),
+    Uid(u64),
+    Array(Vec<Value>),
+    Dict(Vec<(String, Value)>),
+}
+
+impl Value {
+    pub fn get(&self, key: &str) -> Option<&Value> {
+        match self {
+            Value::Dict(entries) => {
+                entries.iter().find(|(k, _)| k == key).map(|(_, v)| v)
+            },
+            _ => None,
+        }
+    }
+}
+
+struct Parser<'a> {
+    data: &'a [u8],
+    offsets: Vec<usize>,
+    ref_size: usize,
+    // Objects parsed so far. Each reference takes at least a byte, so an
+    // honest file never needs more visits than it has bytes; shared refs
+    // that would blow that up (each array holding the next one twice, ...)
+    // are rejected.
+    visits: Cell<usize>,
+}
+
+pub fn parse(data: &[u8]) -> Option<Value> {
+    if data.len() < 8 + 32 || &data[0..8] != b"bplist00" {
+        return None;
+    }
+    let trailer = &data[data.len() - 32..];
+    let offset_size = trailer[6] as usize;
+    let ref_size = trailer[7] as usize;
+    let num_objects = read_uint(&trailer[8..16])? as usize;
+    let top_object = read_uint(&trailer[16..24])? as usize;
+    let table_offset = read_uint(&trailer[24..32])? as usize;
+    if offset_size == 0 || ref_size == 0 || top_object >= num_objects {
+        return None;
+    }
+
+    let table_end = table_offset.checked_add(num_objects.checked_mul(offset_size)?)?;
+    let table = data.get(table_offset..table_end)?;
+    let mut offsets = Vec::with_capacity(num_objects);
+    for entry in table.chunks(offset_size) {
+        offsets.push(read_uint(entry)? as usize);
+    }
+
+    let parser = Parser { data, offsets, ref_size, visits: Cell::new(0) };
+    parser.object(top_object, 0)
+}
+
+fn read_uint(bytes: &[u8]) -> Option<u64> {
+    if bytes.len() > 8 {
+        return None;
+    }
+    Some(bytes.iter().fold(0, |acc, &b| (acc << 8) | b as u64))
+}
+
+impl<'a> Parser<'a> {
+    fn bytes(&self, start: usize, len: usize) -> Option<&'a [u8]> {
+        self.data.get(start..start.checked_add(len)?)
+    }
+
+    // Returns the length encoded in a marker and where the payload starts.
+    fn length(&self, marker_low: u8, offset: usize) -> Option<(usize, usize)> {
+        if marker_low != 0x0f {
+            return Some((marker_low as usize, offset + 1));
+        }
+        let int_marker = *self.data.get(offset + 1)?;
+        if int_marker >> 4 != 0x1 {
+            return None;
+        }
+        let size = 1 << (int_marker & 0x0f);
+        let len = read_uint(self.bytes(offset + 2, size)?)? as usize;
+        Some((len, offset + 2 + size))
+    }
+
+    fn refs(&self, start: usize, count: usize) -> Option<Vec<usize>> {
+        let bytes = self.bytes(start, count.checked_mul(self.ref_size)?)?;
+        bytes.chunks(self.ref_size).map(|r| read_uint(r).map(|v| v as usize)).collect()
+    }
+
+    fn object(&self, index: usize, depth: usize) -> Option<Value> {
+        if depth > MAX_DEPTH || self.visits.get() >= self.data.len() {
+            return None;
+        }
+        self.visits.set(self.visits.get() + 1);
+        let offset = *self.offsets.get(index)?;
+        let marker = *self.data.get(offset)?;
+        let low = marker & 0x0f;
+        match marker >> 4 {
+            0x0 => match marker {
+                0x00 => Some(Value::Null),
+                0x08 => Some(Value::Bool(false)),
+                0x09 => Some(Value::Bool(true)),
+                _    => None,
+            },
+            0x1 => {
+                let bytes = self.bytes(offset + 1, 1 << low)?;
+                Some(Value::Integer(read_uint(bytes)? as i64))
+            },
+            0x2 => {
+                let bytes = self.bytes(offset + 1, 1 << low)?;
+                match bytes.len() {
+                    4 => Some(Value::Real(f32::from_bits(read_uint(bytes)? as u32) as f64)),
+                    8 => Some(Value::Real(f64::from_bits(read_uint(bytes)?))),
+                    _ => None,
+                }
+            },
+            0x4 => {
+                let (len, start) = self.length(low, offset)?;
+                Some(Value::Data(self.bytes(start, len)?.to_vec()))
+            },
+            0x5 => {
+                let (len, start) = self.length(low, offset)?;
+                let text = str::from_utf8(self.bytes(start, len)?).ok()?;
+                Some(Value::String(text.to_string()))
+            },
+            0x6 => {
+                let (len, start) = self.length(low, offset)?;
+                let units: Vec<u16> = self.bytes(start, len.checked_mul(2)?)?
+                    .chunks(2)
+                    .map(|pair| ((pair[0] as u16) << 8) | pair[1] as u16)
+                    .collect();
+                Some(Value::String(String::from_utf16(&units).ok()?))
+            },
+            0x8 => {
+                let bytes = self.bytes(offset + 1, low as usize + 1)?;
+                Some(Value::Uid(read_uint(bytes)?))
+            },
+            0xa => {
+                let (len, start) = self.length(low, offset)?;
+                let items = self.refs(start, len)?;
+                let values: Option<Vec<_>> = items.into_iter()
+                    .map(|i| self.object(i, depth + 1))
+                    .collect();
+                values.map(Value::Array)
+            },
+            0xd => {
+                let (len, start) = self.length(low, offset)?;
+                let keys = self.refs(start, len)?;
+                let values = self.refs(start + len * self.ref_size, len)?;
+                let mut entries = Vec::with_capacity(len);
+                for (k, v) in keys.into_iter().zip(values) {
+                    let key = match self.object(k, depth + 1)? {
+                        Value::String(key) => key,
+                        _                  => return None,
+                    };
+                    entries.push((key, self.object(v, depth + 1)?));
+                }
+                Some(Value::Dict(entries))
+            },
+            _ => None,
+        }
+    }
+}
+
+/// Decodes the base64 payload of an XML plist `<data>` element, which is
+/// usually wrapped over several indented lines.
+pub fn decode_base64(s: &str) -> Option<Vec<u8>> {
+    let mut bytes = Vec::with_capacity(s.len() * 3 / 4);
+    let mut buffer: u32 = 0;
+    let mut bits = 0;
+    for c in s.bytes() {
+        let value = match c {
+            b'A'..=b'Z' => c - b'A',
+            b'a'..=b'z' => c - b'a' + 26,
+            b'0'..=b'9' => c - b'0' + 52,
+            b'+'        => 62,
+            b'/'        => 63,
+            b'='        => break,
+            b' ' | b'\t' | b'\r' | b'\n' => continue,
+            _           => return None,
+        };
+        buffer = (buffer << 6) | value as u32;
+        bits += 6;
+        if bits >= 8 {
+            bits -= 8;
+            bytes.push((buffer >> bits) as u8);
+            buffer &= (1 << bits) - 1;
+        }
+    }
+    Some(bytes)
+}
@@ -1,45 +1,176 @@
 extern crate xml;
+extern crate json;
+#[cfg(feature = "gzip")]
+extern crate flate2;
+#[cfg(feature = "yaml")]
+extern crate yaml_rust;
 
+use std::borrow::Cow;
+use std::collections::HashSet;
+use std::error::Error;
+use std::fmt;
+use std::fs;
+use std::io::{self, Read, Write};
 use std::num::ParseIntError;
+use std::path::{Path, PathBuf};
+use std::str::{self, FromStr, Utf8Error};
+#[cfg(feature = "yaml")]
+use self::yaml_rust::{Yaml, YamlLoader};
 use self::xml::{Element, Xml};
+use bplist;
 
+#[derive(Debug, Clone, Copy, PartialEq)]
 pub enum ColorSchemeFormat {
     ITerm,
     Mintty,
+    VSCode,
+    Foot,
+    Json,
+    TerminalApp,
 }
 
 impl ColorSchemeFormat {
     pub fn from_string(s: &str) -> Option<Self> {
         match s {
-            "iterm" => Some(ColorSchemeFormat::ITerm),
-            "mintty" => Some(ColorSchemeFormat::Mintty),
-            _        => None,
+            "iterm"        => Some(ColorSchemeFormat::ITerm),
+            "mintty"       => Some(ColorSchemeFormat::Mintty),
+            "vscode"       => Some(ColorSchemeFormat::VSCode),
+            "foot"         => Some(ColorSchemeFormat::Foot),
+            "json"         => Some(ColorSchemeFormat::Json),
+            "terminal-app" => Some(ColorSchemeFormat::TerminalApp),
+            _              => None,
         }
     }
 
+    /// Guesses from the file extension, ignoring case, so
+    /// `Dracula.ITERMCOLORS` matches but `my.itermcolors.backup` doesn't.
+    /// With the `gzip` feature, a trailing `.gz` is looked through.
     pub fn from_filename(s: &str) -> Option<Self> {
-        if s.contains(".itermcolors") {
-            return Some(ColorSchemeFormat::ITerm);
-        } else if s.contains(".minttyrc") {
-            return Some(ColorSchemeFormat::Mintty);
-        } else {
+        let name = Path::new(s).file_name()?.to_string_lossy().to_lowercase();
+        let name = strip_gz(&name);
+        let extension = match Path::new(name).extension() {
+            Some(extension) => extension.to_string_lossy().into_owned(),
+            // `Path` doesn't see an extension on a bare dotfile like `.minttyrc`.
+            None => name.strip_prefix('.')?.to_string(),
+        };
+        match extension.as_ref() {
+            "itermcolors" => Some(ColorSchemeFormat::ITerm),
+            "minttyrc"    => Some(ColorSchemeFormat::Mintty),
+            _             => None,
+        }
+    }
+
+    /// Sniffs the format from the content itself, for input without a
+    /// telling filename such as stdin.
+    pub fn detect(content: &str) -> Option<Self> {
+        let trimmed = content.trim_start();
+        if trimmed.starts_with('<') {
+            if content.contains("<key>Ansi 0 Color</key>") || content.contains(" Component</key>") {
+                return Some(ColorSchemeFormat::ITerm);
+            }
+            if content.contains("<key>ANSI") || content.contains("<key>TextColor</key>") {
+                return Some(ColorSchemeFormat::TerminalApp);
+            }
             return None;
         }
+        if trimmed.starts_with('{') {
+            let root = json::parse(content).ok()?;
+            if root.has_key("palette") || root.has_key("normal") {
+                return Some(ColorSchemeFormat::Json);
+            }
+            let settings = root.has_key("workbench.colorCustomizations")
+                || root.entries().any(|(key, _)| key.starts_with("terminal."));
+            return if settings { Some(ColorSchemeFormat::VSCode) } else { None };
+        }
+        if content.lines().any(|line| line.trim() == "[colors]") {
+            return Some(ColorSchemeFormat::Foot);
+        }
+        let mintty = content.lines().any(|line| {
+            let mut parts = line.splitn(2, '=');
+            let key = parts.next().unwrap().trim();
+            let value = parts.next().unwrap_or("").trim();
+            !key.is_empty() && Color::from_string(value).is_ok()
+        });
+        if mintty { Some(ColorSchemeFormat::Mintty) } else { None }
     }
 }
 
 // http://jadpole.github.io/rust/many-error-types
-#[derive(Debug, PartialEq)]
+#[derive(Debug)]
 pub enum ColorError {
     InvalidFormat,
     ParseInt(ParseIntError),
+    /// Keys a format requires but the input lacks, e.g. `regular5`.
+    MissingKeys(Vec<String>),
+    Utf8(Utf8Error),
+    /// No format was given and none could be detected.
+    UnknownFormat,
+    /// A line-based file had a bad line; `line` counts from 1.
+    InvalidLine { line: usize, text: String, cause: Box<ColorError> },
+    /// A keyed file had a bad entry, e.g. an iTerm `Ansi 1 Color`.
+    InvalidKey { key: String, cause: Box<ColorError> },
+    Io(io::Error),
+}
+
+// `io::Error` isn't `PartialEq`, so `Io` errors compare by kind.
+impl PartialEq for ColorError {
+    fn eq(&self, other: &ColorError) -> bool {
+        match (self, other) {
+            (ColorError::InvalidFormat, ColorError::InvalidFormat) => true,
+            (ColorError::ParseInt(a), ColorError::ParseInt(b))     => a == b,
+            (ColorError::MissingKeys(a), ColorError::MissingKeys(b)) => a == b,
+            (ColorError::Utf8(a), ColorError::Utf8(b))             => a == b,
+            (ColorError::UnknownFormat, ColorError::UnknownFormat) => true,
+            (
+                ColorError::InvalidLine { line, text, cause },
+                ColorError::InvalidLine { line: other_line, text: other_text, cause: other_cause },
+            ) => line == other_line && text == other_text && cause == other_cause,
+            (
+                ColorError::InvalidKey { key, cause },
+                ColorError::InvalidKey { key: other_key, cause: other_cause },
+            ) => key == other_key && cause == other_cause,
+            (ColorError::Io(a), ColorError::Io(b))                 => a.kind() == b.kind(),
+            _ => false,
+        }
+    }
 }
 
-#[derive(Debug, Default, PartialEq)]
+impl fmt::Display for ColorError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            ColorError::InvalidFormat        => write!(f, "invalid format"),
+            ColorError::ParseInt(e)          => write!(f, "invalid number: {}", e),
+            ColorError::MissingKeys(keys)    => write!(f, "missing keys: {}", keys.join(", ")),
+            ColorError::Utf8(e)              => write!(f, "invalid UTF-8: {}", e),
+            ColorError::UnknownFormat        => write!(f, "unknown format"),
+            ColorError::InvalidLine { line, text, cause } => {
+                write!(f, "invalid color on line {}: '{}': {}", line, text, cause)
+            },
+            ColorError::InvalidKey { key, cause } => write!(f, "invalid color for key '{}': {}", key, cause),
+            ColorError::Io(e)                => write!(f, "I/O error: {}", e),
+        }
+    }
+}
+
+impl Error for ColorError {
+    fn source(&self) -> Option<&(dyn Error + 'static)> {
+        match self {
+            ColorError::ParseInt(e)                 => Some(e),
+            ColorError::Utf8(e)                     => Some(e),
+            ColorError::Io(e)                       => Some(e),
+            ColorError::InvalidLine { cause, .. }   => Some(&**cause),
+            ColorError::InvalidKey { cause, .. }    => Some(&**cause),
+            _                                       => None,
+        }
+    }
+}
+
+#[derive(Debug, Default, PartialEq, Clone, Copy)]
 pub struct Color {
     pub red: u8,
     pub green: u8,
     pub blue: u8,
+    pub alpha: Option<u8>,
 }
 
 impl Color {
@@ -51,185 +182,1987 @@ impl Color {
         let red = Color::parse_int(rgb[0])?;
         let green = Color::parse_int(rgb[1])?;
         let blue = Color::parse_int(rgb[2])?;
-        let color = Color { red: red, green: green, blue: blue };
+        let color = Color { red: red, green: green, blue: blue, alpha: None };
         Ok(color)
     }
 
+    pub fn from_string_rgba(s: &str) -> Result<Self, ColorError> {
+        let rgba: Vec<_> = s.split(",").collect();
+        if rgba.len() != 4 {
+            return Err(ColorError::InvalidFormat);
+        }
+        let mut color = Color::from_string(&rgba[0..3].join(","))?;
+        color.alpha = Some(Color::parse_int(rgba[3])?);
+        Ok(color)
+    }
+
+    /// A more lenient `from_string`: three components separated by commas or
+    /// whitespace, as `0`-`255` integers or, when any has a decimal point and
+    /// none is over 1, as `0`-`1` floats like `0.5 0.3 1.0`.
+    pub fn from_components(s: &str) -> Result<Self, ColorError> {
+        let components: Vec<&str> = if s.contains(',') {
+            s.split(',').map(str::trim).collect()
+        } else {
+            s.split_whitespace().collect()
+        };
+        if components.len() != 3 {
+            return Err(ColorError::InvalidFormat);
+        }
+
+        let floats: Option<Vec<f32>> = components.iter().map(|c| c.parse().ok()).collect();
+        let is_fraction = match floats {
+            Some(ref floats) => {
+                components.iter().any(|c| c.contains('.')) && floats.iter().all(|&v| v <= 1.0)
+            },
+            None => false,
+        };
+        let channels = if is_fraction {
+            let floats = floats.unwrap();
+            if floats.iter().any(|&v| v < 0.0) {
+                return Err(ColorError::InvalidFormat);
+            }
+            floats.iter().map(|&v| (v * 255.0).round() as u8).collect()
+        } else {
+            components.iter().map(|c| Color::parse_int(c)).collect::<Result<Vec<u8>, _>>()?
+        };
+        Ok(Color { red: channels[0], green: channels[1], blue: channels[2], alpha: None })
+    }
+
+    /// Parses `rrggbb`, `rrggbbaa` or the CSS shorthands `rgb` and `rgba`,
+    /// optionally prefixed with `#` or `0x`.
+    pub fn from_hex(s: &str) -> Result<Self, ColorError> {
+        let digits = s.strip_prefix('#')
+            .or_else(|| s.strip_prefix("0x"))
+            .unwrap_or(s);
+        // `from_str_radix` would also take a sign, so `#+f+f+f` must be
+        // turned away here.
+        if !digits.bytes().all(|b| b.is_ascii_hexdigit()) {
+            return Err(ColorError::InvalidFormat);
+        }
+        // CSS shorthand: `#1a2` is `#11aa22`, `#1a2f` is `#11aa22ff`.
+        let expanded: String;
+        let digits = match digits.len() {
+            3 | 4 => {
+                expanded = digits.chars().flat_map(|c| vec![c, c]).collect();
+                &expanded
+            },
+            6 | 8 => digits,
+            _     => return Err(ColorError::InvalidFormat),
+        };
+        let red = Color::parse_hex_byte(&digits[0..2])?;
+        let green = Color::parse_hex_byte(&digits[2..4])?;
+        let blue = Color::parse_hex_byte(&digits[4..6])?;
+        let alpha = if digits.len() == 8 {
+            Some(Color::parse_hex_byte(&digits[6..8])?)
+        } else {
+            None
+        };
+        Ok(Color { red, green, blue, alpha })
+    }
+
     fn parse_int(s: &str) -> Result<u8, ColorError> {
         s.parse().or_else(|e| Err(ColorError::ParseInt(e)))
     }
 
+    fn parse_hex_byte(s: &str) -> Result<u8, ColorError> {
+        u8::from_str_radix(s, 16).map_err(ColorError::ParseInt)
+    }
+
+    /// xterm's default for an ANSI slot named as in `ColorScheme::iter`,
+    /// e.g. `red` or `bright_blue`.
+    pub fn from_ansi_name(name: &str) -> Option<Color> {
+        let (red, green, blue) = match name {
+            "black"          => (0x00, 0x00, 0x00),
+            "red"            => (0xcd, 0x00, 0x00),
+            "green"          => (0x00, 0xcd, 0x00),
+            "yellow"         => (0xcd, 0xcd, 0x00),
+            "blue"           => (0x00, 0x00, 0xee),
+            "magenta"        => (0xcd, 0x00, 0xcd),
+            "cyan"           => (0x00, 0xcd, 0xcd),
+            "white"          => (0xe5, 0xe5, 0xe5),
+            "bright_black"   => (0x7f, 0x7f, 0x7f),
+            "bright_red"     => (0xff, 0x00, 0x00),
+            "bright_green"   => (0x00, 0xff, 0x00),
+            "bright_yellow"  => (0xff, 0xff, 0x00),
+            "bright_blue"    => (0x5c, 0x5c, 0xff),
+            "bright_magenta" => (0xff, 0x00, 0xff),
+            "bright_cyan"    => (0x00, 0xff, 0xff),
+            "bright_white"   => (0xff, 0xff, 0xff),
+            _                => return None,
+        };
+        Some(Color { red, green, blue, alpha: None })
+    }
+
+    /// The same color without alpha, for formats that can't express it.
+    pub fn opaque(&self) -> Color {
+        Color { alpha: None, ..*self }
+    }
+
+    /// The canonical form of the color: an alpha of 255 is dropped, since
+    /// it's the same as none. The `u8` channels can't go out of range.
+    pub fn normalize(&self) -> Color {
+        match self.alpha {
+            Some(255) => self.opaque(),
+            _         => *self,
+        }
+    }
+
+    /// The inverse of `from_string`: `12,3,255`, ignoring alpha.
+    pub fn to_rgb_string(&self) -> String {
+        format!("{},{},{}", self.red, self.green, self.blue)
+    }
+
     pub fn to_hex(&self) -> String {
-        format!("0x{:>02x}{:>02x}{:>02x}", self.red, self.green, self.blue)
+        self.format_hex("0x")
+    }
+
+    pub fn to_css_hex(&self) -> String {
+        self.format_hex("#")
+    }
+
+    /// Hue in degrees `[0, 360)`, saturation and lightness in `[0, 1]`.
+    pub fn to_hsl(&self) -> (f32, f32, f32) {
+        let r = self.red as f32 / 255.0;
+        let g = self.green as f32 / 255.0;
+        let b = self.blue as f32 / 255.0;
+        let max = r.max(g).max(b);
+        let min = r.min(g).min(b);
+        let lightness = (max + min) / 2.0;
+        let delta = max - min;
+        if delta == 0.0 {
+            return (0.0, 0.0, lightness);
+        }
+
+        let saturation = delta / (1.0 - (2.0 * lightness - 1.0).abs());
+        let hue = if max == r {
+            60.0 * (((g - b) / delta) % 6.0)
+        } else if max == g {
+            60.0 * ((b - r) / delta + 2.0)
+        } else {
+            60.0 * ((r - g) / delta + 4.0)
+        };
+        let hue = if hue < 0.0 { hue + 360.0 } else { hue };
+        (hue, saturation, lightness)
+    }
+
+    /// Inverse of `to_hsl`; out-of-range inputs are wrapped (hue) or clamped.
+    pub fn from_hsl(hue: f32, saturation: f32, lightness: f32) -> Self {
+        let hue = ((hue % 360.0) + 360.0) % 360.0;
+        let saturation = saturation.clamp(0.0, 1.0);
+        let lightness = lightness.clamp(0.0, 1.0);
+
+        let chroma = (1.0 - (2.0 * lightness - 1.0).abs()) * saturation;
+        let x = chroma * (1.0 - ((hue / 60.0) % 2.0 - 1.0).abs());
+        let m = lightness - chroma / 2.0;
+        let (r, g, b) = match (hue / 60.0) as u8 {
+            0 => (chroma, x, 0.0),
+            1 => (x, chroma, 0.0),
+            2 => (0.0, chroma, x),
+            3 => (0.0, x, chroma),
+            4 => (x, 0.0, chroma),
+            _ => (chroma, 0.0, x),
+        };
+        let channel = |v: f32| ((v + m) * 255.0).round().clamp(0.0, 255.0) as u8;
+        Color { red: channel(r), green: channel(g), blue: channel(b), alpha: None }
+    }
+
+    /// Raises the HSL lightness by `amount`, saturating at white.
+    pub fn lighten(&self, amount: f32) -> Color {
+        let (_, _, lightness) = self.to_hsl();
+        self.with_lightness(lightness + amount)
+    }
+
+    /// Lowers the HSL lightness by `amount`, saturating at black.
+    pub fn darken(&self, amount: f32) -> Color {
+        self.lighten(-amount)
+    }
+
+    fn with_lightness(&self, lightness: f32) -> Color {
+        let (hue, saturation, _) = self.to_hsl();
+        Color { alpha: self.alpha, ..Color::from_hsl(hue, saturation, lightness) }
+    }
+
+    /// WCAG relative luminance, from 0 for black to 1 for white.
+    /// https://www.w3.org/TR/WCAG20/#relativeluminancedef
+    pub fn relative_luminance(&self) -> f32 {
+        let linear = |v: u8| {
+            let v = v as f32 / 255.0;
+            if v <= 0.03928 { v / 12.92 } else { ((v + 0.055) / 1.055).powf(2.4) }
+        };
+        0.2126 * linear(self.red) + 0.7152 * linear(self.green) + 0.0722 * linear(self.blue)
+    }
+
+    /// WCAG contrast ratio, from 1 for identical colors to 21 for black on
+    /// white. https://www.w3.org/TR/WCAG20/#contrast-ratiodef
+    pub fn contrast_ratio(&self, other: &Color) -> f32 {
+        let a = self.relative_luminance();
+        let b = other.relative_luminance();
+        (a.max(b) + 0.05) / (a.min(b) + 0.05)
+    }
+
+    /// Whether `relative_luminance` is below 0.5.
+    pub fn is_dark(&self) -> bool {
+        self.relative_luminance() < 0.5
+    }
+
+    /// Linear interpolation from `self` (`t = 0`) to `other` (`t = 1`), with
+    /// `t` clamped to that range. A missing alpha counts as opaque.
+    pub fn blend(&self, other: &Color, t: f32) -> Color {
+        let t = t.clamp(0.0, 1.0);
+        let mix = |a: u8, b: u8| (a as f32 + (b as f32 - a as f32) * t).round() as u8;
+        let alpha = match (self.alpha, other.alpha) {
+            (None, None) => None,
+            (a, b)       => Some(mix(a.unwrap_or(255), b.unwrap_or(255))),
+        };
+        Color {
+            red: mix(self.red, other.red),
+            green: mix(self.green, other.green),
+            blue: mix(self.blue, other.blue),
+            alpha,
+        }
+    }
+
+    /// Rounds each channel to the nearest of the `2^bits_per_channel` evenly
+    /// spaced levels between 0 and 255. `bits_per_channel` is clamped to 1..=8.
+    pub fn quantize(&self, bits_per_channel: u8) -> Color {
+        let levels = ((1u16 << bits_per_channel.clamp(1, 8)) - 1) as f32;
+        let channel = |v: u8| ((v as f32 * levels / 255.0).round() * 255.0 / levels).round() as u8;
+        Color {
+            red: channel(self.red),
+            green: channel(self.green),
+            blue: channel(self.blue),
+            alpha: self.alpha,
+        }
+    }
+
+    /// Gray of the same perceived brightness, weighting the channels
+    /// 0.299 / 0.587 / 0.114 as in ITU-R BT.601. Alpha is kept.
+    pub fn grayscale(&self) -> Color {
+        let gray = (0.299 * self.red as f32 + 0.587 * self.green as f32 + 0.114 * self.blue as f32)
+            .round()
+            .min(255.0) as u8;
+        Color { red: gray, green: gray, blue: gray, alpha: self.alpha }
+    }
+
+    // Every exported hex color goes through here: lowercase, after
+    // `normalize`, with the format's own prefix.
+    fn format_hex(&self, prefix: &str) -> String {
+        let color = self.normalize();
+        let rgb = format!("{}{:02x}{:02x}{:02x}", prefix, color.red, color.green, color.blue);
+        match color.alpha {
+            Some(alpha) => format!("{}{:02x}", rgb, alpha),
+            None        => rgb,
+        }
     }
 }
 
-fn extract_text(element: &Element) -> &str {
-    let first = &element.children[0];
-    match first {
-        &Xml::CharacterNode(ref text) => text,
-        _ => panic!("Not an chracter node: {}", first),
+/// `#rrggbb`, or `#rrggbbaa` when alpha is set; see `Color::to_css_hex`.
+impl fmt::Display for Color {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{}", self.to_css_hex())
+    }
+}
+
+/// Hex as accepted by `from_hex`, or `r,g,b` as accepted by `from_string`.
+impl FromStr for Color {
+    type Err = ColorError;
+
+    fn from_str(s: &str) -> Result<Self, ColorError> {
+        if s.contains(',') {
+            Color::from_string(s)
+        } else {
+            Color::from_hex(s)
+        }
     }
 }
 
-#[derive(Default)]
-pub struct ColorScheme {
-    foreground: Color,
-    background: Color,
+fn iterm_color_entry(name: &str, color: &Color) -> String {
+    let alpha = match color.alpha {
+        Some(alpha) => format!("\t\t<key>Alpha Component</key>\n\t\t<real>{}</real>\n", alpha as f64 / 255.0),
+        None        => String::new(),
+    };
+    format!("\t<key>{}</key>
+\t<dict>
+{}\t\t<key>Blue Component</key>
+\t\t<real>{}</real>
+\t\t<key>Green Component</key>
+\t\t<real>{}</real>
+\t\t<key>Red Component</key>
+\t\t<real>{}</real>
+\t</dict>
+",
+        name,
+        alpha,
+        color.blue as f64 / 255.0,
+        color.green as f64 / 255.0,
+        color.red as f64 / 255.0,
+    )
+}
 
-    black: Color,
-    red: Color,
-    green: Color,
-    yellow: Color,
-    blue: Color,
-    magenta: Color,
-    cyan: Color,
-    white: Color,
+// xterm's default for palette entries 16-255.
+fn xterm_indexed_color(index: u8) -> Color {
+    if index >= 232 {
+        let level = 8 + 10 * (index - 232);
+        return Color { red: level, green: level, blue: level, alpha: None };
+    }
+    let cube = index - 16;
+    let level = |v: u8| if v == 0 { 0 } else { 55 + 40 * v };
+    Color {
+        red: level(cube / 36),
+        green: level(cube / 6 % 6),
+        blue: level(cube % 6),
+        alpha: None,
+    }
+}
 
-    bright_black: Color,
-    bright_red: Color,
-    bright_green: Color,
-    bright_yellow: Color,
-    bright_blue: Color,
-    bright_magenta: Color,
-    bright_cyan: Color,
-    bright_white: Color,
+// A stable UUID-formatted hash of `s`, from two FNV-1a passes with
+// different offsets.
+fn guid(s: &str) -> String {
+    let fnv = |offset: u64| s.bytes().fold(offset, |hash, b| (hash ^ b as u64).wrapping_mul(0x100000001b3));
+    let high = fnv(0xcbf29ce484222325);
+    let low = fnv(0x84222325cbf29ce4);
+    format!(
+        "{:08X}-{:04X}-{:04X}-{:04X}-{:012X}",
+        high >> 32, (high >> 16) & 0xffff, high & 0xffff, low >> 48, low & 0xffffffffffff
+    )
+}
+
+#[cfg(feature = "gzip")]
+fn decompress(data: &[u8]) -> Result<Cow<'_, [u8]>, ColorError> {
+    if !data.starts_with(&[0x1f, 0x8b]) {
+        return Ok(Cow::Borrowed(data));
+    }
+    let mut decompressed = Vec::new();
+    flate2::read::GzDecoder::new(data)
+        .read_to_end(&mut decompressed)
+        .map_err(|_| ColorError::InvalidFormat)?;
+    Ok(Cow::Owned(decompressed))
+}
+
+#[cfg(not(feature = "gzip"))]
+fn decompress(data: &[u8]) -> Result<Cow<'_, [u8]>, ColorError> {
+    Ok(Cow::Borrowed(data))
+}
+
+// The file name with a trailing `.gz`, in any case, taken off.
+#[cfg(feature = "gzip")]
+fn strip_gz(name: &str) -> &str {
+    let split = name.len().saturating_sub(3);
+    match name.get(split..) {
+        Some(suffix) if suffix.eq_ignore_ascii_case(".gz") => &name[..split],
+        _ => name,
+    }
+}
+
+#[cfg(not(feature = "gzip"))]
+fn strip_gz(name: &str) -> &str {
+    name
+}
+
+// Pairs up the `<key>` elements of a plist `<dict>` with the value element
+// following each, whatever its type.
+fn dict_entries(dict: &Element) -> Vec<(String, &Element)> {
+    let mut entries = Vec::new();
+    let mut key = None;
+    for child in &dict.children {
+        if let Xml::ElementNode(element) = child {
+            match key.take() {
+                None if element.name == "key" => key = Some(element.content_str()),
+                None                          => (),
+                Some(name)                    => entries.push((name, element)),
+            }
+        }
+    }
+    entries
+}
+
+// Terminal.app stores each color as an NSKeyedArchiver-encoded NSColor, a
+// binary plist whose root object holds the components as a string of floats.
+fn decode_nscolor(data: &Element) -> Result<Color, ColorError> {
+    let archive = bplist::decode_base64(&data.content_str())
+        .and_then(|bytes| bplist::parse(&bytes))
+        .ok_or(ColorError::InvalidFormat)?;
+    let root = match archive.get("$top").and_then(|top| top.get("root")) {
+        Some(&bplist::Value::Uid(uid)) => uid as usize,
+        _ => return Err(ColorError::InvalidFormat),
+    };
+    let color = match archive.get("$objects") {
+        Some(bplist::Value::Array(objects)) if root < objects.len() => &objects[root],
+        _ => return Err(ColorError::InvalidFormat),
+    };
+
+    let (components, grayscale) = match (color.get("NSRGB"), color.get("NSWhite")) {
+        (Some(bplist::Value::Data(rgb)), _) => (rgb, false),
+        (_, Some(bplist::Value::Data(white))) => (white, true),
+        _ => return Err(ColorError::InvalidFormat),
+    };
+    let text = String::from_utf8_lossy(components);
+    let values = text.trim_end_matches('\0')
+        .split_whitespace()
+        .map(|v| v.parse::<f32>().map_err(|_| ColorError::InvalidFormat))
+        .collect::<Result<Vec<_>, _>>()?;
+    let channel = |v: f32| (v * 255.0).round().clamp(0.0, 255.0) as u8;
+    let (rgb, alpha) = match (grayscale, values.len()) {
+        (false, 3) => ([values[0], values[1], values[2]], None),
+        (false, 4) => ([values[0], values[1], values[2]], Some(values[3])),
+        (true, 1)  => ([values[0]; 3], None),
+        (true, 2)  => ([values[0]; 3], Some(values[1])),
+        _          => return Err(ColorError::InvalidFormat),
+    };
+    Ok(Color {
+        red: channel(rgb[0]),
+        green: channel(rgb[1]),
+        blue: channel(rgb[2]),
+        alpha: alpha.and_then(|a| if a < 1.0 { Some(channel(a)) } else { None }),
+    })
+}
+
+/// Rewrites the color settings of an existing `.minttyrc` with those of
+/// `scheme`, keeping every other line (`Font`, `Locale`, `ThemeFile`, comments)
+/// as it is. Colors the file doesn't set yet are appended at the end; like any
+/// explicit setting, they take precedence over a `ThemeFile`.
+pub fn patch_minttyrc(original: &str, scheme: &ColorScheme) -> String {
+    let newline = if original.contains("\r\n") { "\r\n" } else { "\n" };
+    let mut entries: Vec<(&str, Color, bool)> = scheme.minttyrc_entries()
+        .into_iter()
+        .map(|(key, color)| (key, color, false))
+        .collect();
+
+    let mut patched = String::new();
+    for line in original.lines() {
+        let setting = line.split('#').next().unwrap_or("");
+        let key = setting.split('=').next().unwrap_or("").trim();
+        match entries.iter_mut().find(|entry| entry.0 == key) {
+            Some(entry) => {
+                let color = entry.1;
+                patched.push_str(&format!("{}={}", key, color.to_rgb_string()));
+                entry.2 = true;
+            },
+            None => patched.push_str(line),
+        }
+        patched.push_str(newline);
+    }
+    for &(key, color, written) in &entries {
+        if !written {
+            patched.push_str(&format!("{}={}{}", key, color.to_rgb_string(), newline));
+        }
+    }
+    patched
+}
+
+// The shallowest mapping under a `colors` key, breadth-first, so the first
+// in document order wins between equally deep ones.
+#[cfg(feature = "yaml")]
+fn find_alacritty_colors(yaml: &Yaml) -> Option<&Yaml> {
+    let mut level = vec![yaml];
+    while !level.is_empty() {
+        let hashes: Vec<_> = level.into_iter().filter_map(Yaml::as_hash).collect();
+        for hash in &hashes {
+            if let Some(colors) = hash.get(&Yaml::String("colors".to_string())) {
+                if colors.as_hash().is_some() {
+                    return Some(colors);
+                }
+            }
+        }
+        level = hashes.into_iter().flat_map(|hash| hash.values()).collect();
+    }
+    None
+}
+
+/// Reads a whole scheme in `format` from `input` and writes it to `output`
+/// as Alacritty YAML.
+pub fn convert_reader<R: Read, W: Write>(
+    mut input: R,
+    mut output: W,
+    format: ColorSchemeFormat,
+) -> Result<(), ColorError> {
+    let mut data = Vec::new();
+    input.read_to_end(&mut data).map_err(ColorError::Io)?;
+    let scheme = ColorScheme::from_bytes(&data, Some(format))?;
+    output.write_all(scheme.to_yaml().as_bytes()).map_err(ColorError::Io)
+}
+
+/// The outcome of `convert_dir`.
+#[derive(Debug, Default)]
+pub struct ConversionReport {
+    pub succeeded: usize,
+    /// Each file that couldn't be converted, in the order they were tried.
+    pub failed: Vec<(PathBuf, ColorError)>,
+}
+
+impl fmt::Display for ConversionReport {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{} converted, {} failed", self.succeeded, self.failed.len())?;
+        for (path, error) in &self.failed {
+            write!(f, "\n  {}: {}", path.display(), error)?;
+        }
+        Ok(())
+    }
+}
+
+/// Converts every file directly in `input` to Alacritty YAML, written to
+/// `output` as `<file name>.yml`, e.g. `Dracula.itermcolors.yml` for
+/// `Dracula.itermcolors`. The source extension is kept so that schemes of the
+/// same name in different formats don't overwrite each other; with the
+/// `gzip` feature, a trailing `.gz` is dropped, as `from_filename` looks
+/// through it. Formats are guessed as by `ColorScheme::from_bytes`, with the
+/// file name as a hint. Files that fail are collected in the report rather
+/// than stopping the rest; the `Err` is only for not being able to list
+/// `input`.
+pub fn convert_dir<P: AsRef<Path>, Q: AsRef<Path>>(input: P, output: Q) -> Result<ConversionReport, ColorError> {
+    let mut paths = Vec::new();
+    for entry in fs::read_dir(input).map_err(ColorError::Io)? {
+        let path = entry.map_err(ColorError::Io)?.path();
+        if path.is_file() {
+            paths.push(path);
+        }
+    }
+    paths.sort();
+
+    let mut report = ConversionReport::default();
+    for path in paths {
+        match convert_file(&path, output.as_ref()) {
+            Ok(())     => report.succeeded += 1,
+            Err(error) => report.failed.push((path, error)),
+        }
+    }
+    Ok(report)
+}
+
+fn convert_file(path: &Path, output: &Path) -> Result<(), ColorError> {
+    let data = fs::read(path).map_err(ColorError::Io)?;
+    let name = path.file_name().map(|name| name.to_string_lossy()).unwrap_or_default();
+    let scheme = ColorScheme::from_bytes(&data, ColorSchemeFormat::from_filename(&name))?;
+    let target = output.join(format!("{}.yml", strip_gz(&name)));
+    fs::write(target, scheme.to_yaml()).map_err(ColorError::Io)
+}
+
+/// Palette colors assigned to common syntax highlighting roles, for editors
+/// that theme by meaning rather than by ANSI slot.
+///
+/// | role       | palette color  |
+/// |------------|----------------|
+/// | background | background     |
+/// | foreground | foreground     |
+/// | comment    | bright_black   |
+/// | keyword    | magenta        |
+/// | keyword2   | cyan           |
+/// | string     | green          |
+/// | number     | yellow         |
+/// | literal    | red            |
+/// | function   | blue           |
+/// | operator   | foreground     |
+#[derive(Debug, PartialEq)]
+pub struct SchemeRoles {
+    pub background: Color,
+    pub foreground: Color,
+    pub comment: Color,
+    pub keyword: Color,
+    pub keyword2: Color,
+    pub string: Color,
+    pub number: Color,
+    pub literal: Color,
+    pub function: Color,
+    pub operator: Color,
+}
+
+/// How `ColorScheme::to_svg_with` labels each swatch.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum LabelMode {
+    /// Slot name and hex drawn as `<text>`, which depends on system fonts,
+    /// and in a `<title>` as with `Tooltip`.
+    Text,
+    /// Slot name and hex in an SVG `<title>`, shown on hover.
+    Tooltip,
+    /// Pure swatches, e.g. for thumbnails.
+    None,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct SvgOptions {
+    pub labels: LabelMode,
+}
+
+impl Default for SvgOptions {
+    fn default() -> Self {
+        SvgOptions { labels: LabelMode::Text }
+    }
+}
+
+#[derive(Debug, Default, Clone, Copy, PartialEq)]
+pub struct JsonOptions {
+    /// Adds a `meta` object with `is_dark`, `avg_luminance` and
+    /// `min_contrast`; see `ColorScheme::to_json_with`.
+    pub with_meta: bool,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct YamlOptions {
+    /// The `# Default colors`-style line above each section.
+    pub comments: bool,
+    /// Spaces per nesting level. 0 is taken as 1, since without any
+    /// indentation the sections wouldn't be nested under `colors`.
+    pub indent: usize,
+}
+
+impl Default for YamlOptions {
+    fn default() -> Self {
+        YamlOptions { comments: true, indent: 2 }
+    }
+}
+
+/// A problem in the input that didn't stop it from being parsed.
+#[derive(Debug, Clone, PartialEq)]
+pub enum ValidationWarning {
+    /// The key was given more than once; the last value wins.
+    DuplicateKey(String),
+}
+
+/// How forgiving the `from_*_with` importers are. The plain `from_*`
+/// functions are lenient.
+#[derive(Debug, Default, Clone, Copy, PartialEq)]
+pub struct ParseOptions {
+    /// Rejects unknown keys and requires all 18 colors; otherwise unknown
+    /// keys are skipped and missing colors stay unset.
+    pub strict: bool,
+}
+
+// Standard iTerm keys colortty reads past without using, even when strict.
+const ITERM_UNUSED_KEYS: [&str; 5] = [
+    "Cursor Guide Color",
+    "Link Color",
+    "Selected Text Color",
+    "Selection Color",
+    "Underline Color",
+];
+
+// Each normal ANSI color with its bright and dim variants.
+const ANSI_SLOTS: [(Slot, Slot, Slot); 8] = [
+    (Slot::Black,   Slot::BrightBlack,   Slot::DimBlack),
+    (Slot::Red,     Slot::BrightRed,     Slot::DimRed),
+    (Slot::Green,   Slot::BrightGreen,   Slot::DimGreen),
+    (Slot::Yellow,  Slot::BrightYellow,  Slot::DimYellow),
+    (Slot::Blue,    Slot::BrightBlue,    Slot::DimBlue),
+    (Slot::Magenta, Slot::BrightMagenta, Slot::DimMagenta),
+    (Slot::Cyan,    Slot::BrightCyan,    Slot::DimCyan),
+    (Slot::White,   Slot::BrightWhite,   Slot::DimWhite),
+];
+
+// The tables below map each format's keys to slots, in the order exporters
+// write them. A slot a format has no key for is left out.
+
+const MINTTY_KEYS: [(&str, Slot); 19] = [
+    ("ForegroundColour", Slot::Foreground),
+    ("BackgroundColour", Slot::Background),
+    ("CursorColour",     Slot::Cursor),
+    ("Black",            Slot::Black),
+    ("BoldBlack",        Slot::BrightBlack),
+    ("Red",              Slot::Red),
+    ("BoldRed",          Slot::BrightRed),
+    ("Green",            Slot::Green),
+    ("BoldGreen",        Slot::BrightGreen),
+    ("Yellow",           Slot::Yellow),
+    ("BoldYellow",       Slot::BrightYellow),
+    ("Blue",             Slot::Blue),
+    ("BoldBlue",         Slot::BrightBlue),
+    ("Magenta",          Slot::Magenta),
+    ("BoldMagenta",      Slot::BrightMagenta),
+    ("Cyan",             Slot::Cyan),
+    ("BoldCyan",         Slot::BrightCyan),
+    ("White",            Slot::White),
+    ("BoldWhite",        Slot::BrightWhite),
+];
+
+// Shared by `.itermcolors` and dynamic profiles.
+const ITERM_KEYS: [(&str, Slot); 23] = [
+    ("Ansi 0 Color",      Slot::Black),
+    ("Ansi 1 Color",      Slot::Red),
+    ("Ansi 2 Color",      Slot::Green),
+    ("Ansi 3 Color",      Slot::Yellow),
+    ("Ansi 4 Color",      Slot::Blue),
+    ("Ansi 5 Color",      Slot::Magenta),
+    ("Ansi 6 Color",      Slot::Cyan),
+    ("Ansi 7 Color",      Slot::White),
+    ("Ansi 8 Color",      Slot::BrightBlack),
+    ("Ansi 9 Color",      Slot::BrightRed),
+    ("Ansi 10 Color",     Slot::BrightGreen),
+    ("Ansi 11 Color",     Slot::BrightYellow),
+    ("Ansi 12 Color",     Slot::BrightBlue),
+    ("Ansi 13 Color",     Slot::BrightMagenta),
+    ("Ansi 14 Color",     Slot::BrightCyan),
+    ("Ansi 15 Color",     Slot::BrightWhite),
+    ("Background Color",  Slot::Background),
+    ("Foreground Color",  Slot::Foreground),
+    ("Cursor Color",      Slot::Cursor),
+    ("Badge Color",       Slot::Badge),
+    ("Tab Color",         Slot::Tab),
+    ("Bold Color",        Slot::Bold),
+    ("Cursor Text Color", Slot::CursorText),
+];
+
+const VSCODE_KEYS: [(&str, Slot); 19] = [
+    ("terminal.foreground",        Slot::Foreground),
+    ("terminal.background",        Slot::Background),
+    ("terminal.ansiBlack",         Slot::Black),
+    ("terminal.ansiRed",           Slot::Red),
+    ("terminal.ansiGreen",         Slot::Green),
+    ("terminal.ansiYellow",        Slot::Yellow),
+    ("terminal.ansiBlue",          Slot::Blue),
+    ("terminal.ansiMagenta",       Slot::Magenta),
+    ("terminal.ansiCyan",          Slot::Cyan),
+    ("terminal.ansiWhite",         Slot::White),
+    ("terminal.ansiBrightBlack",   Slot::BrightBlack),
+    ("terminal.ansiBrightRed",     Slot::BrightRed),
+    ("terminal.ansiBrightGreen",   Slot::BrightGreen),
+    ("terminal.ansiBrightYellow",  Slot::BrightYellow),
+    ("terminal.ansiBrightBlue",    Slot::BrightBlue),
+    ("terminal.ansiBrightMagenta", Slot::BrightMagenta),
+    ("terminal.ansiBrightCyan",    Slot::BrightCyan),
+    ("terminal.ansiBrightWhite",   Slot::BrightWhite),
+    ("terminalCursor.foreground",  Slot::Cursor),
+];
+
+const TERMINAL_APP_KEYS: [(&str, Slot); 19] = [
+    ("ANSIBlackColor",         Slot::Black),
+    ("ANSIRedColor",           Slot::Red),
+    ("ANSIGreenColor",         Slot::Green),
+    ("ANSIYellowColor",        Slot::Yellow),
+    ("ANSIBlueColor",          Slot::Blue),
+    ("ANSIMagentaColor",       Slot::Magenta),
+    ("ANSICyanColor",          Slot::Cyan),
+    ("ANSIWhiteColor",         Slot::White),
+    ("ANSIBrightBlackColor",   Slot::BrightBlack),
+    ("ANSIBrightRedColor",     Slot::BrightRed),
+    ("ANSIBrightGreenColor",   Slot::BrightGreen),
+    ("ANSIBrightYellowColor",  Slot::BrightYellow),
+    ("ANSIBrightBlueColor",    Slot::BrightBlue),
+    ("ANSIBrightMagentaColor", Slot::BrightMagenta),
+    ("ANSIBrightCyanColor",    Slot::BrightCyan),
+    ("ANSIBrightWhiteColor",   Slot::BrightWhite),
+    ("BackgroundColor",        Slot::Background),
+    ("TextColor",              Slot::Foreground),
+    ("CursorColor",            Slot::Cursor),
+];
+
+const FOOT_KEYS: [(&str, Slot); 18] = [
+    ("foreground", Slot::Foreground),
+    ("background", Slot::Background),
+    ("regular0",   Slot::Black),
+    ("regular1",   Slot::Red),
+    ("regular2",   Slot::Green),
+    ("regular3",   Slot::Yellow),
+    ("regular4",   Slot::Blue),
+    ("regular5",   Slot::Magenta),
+    ("regular6",   Slot::Cyan),
+    ("regular7",   Slot::White),
+    ("bright0",    Slot::BrightBlack),
+    ("bright1",    Slot::BrightRed),
+    ("bright2",    Slot::BrightGreen),
+    ("bright3",    Slot::BrightYellow),
+    ("bright4",    Slot::BrightBlue),
+    ("bright5",    Slot::BrightMagenta),
+    ("bright6",    Slot::BrightCyan),
+    ("bright7",    Slot::BrightWhite),
+];
+
+// Section and key, for both Alacritty's YAML and TOML. Unset colors are
+// written as black, except in `dim`, which only has the colors that are set.
+const ALACRITTY_KEYS: [(&str, &str, Slot); 26] = [
+    ("primary", "background", Slot::Background),
+    ("primary", "foreground", Slot::Foreground),
+    ("normal",  "black",      Slot::Black),
+    ("normal",  "red",        Slot::Red),
+    ("normal",  "green",      Slot::Green),
+    ("normal",  "yellow",     Slot::Yellow),
+    ("normal",  "blue",       Slot::Blue),
+    ("normal",  "magenta",    Slot::Magenta),
+    ("normal",  "cyan",       Slot::Cyan),
+    ("normal",  "white",      Slot::White),
+    ("bright",  "black",      Slot::BrightBlack),
+    ("bright",  "red",        Slot::BrightRed),
+    ("bright",  "green",      Slot::BrightGreen),
+    ("bright",  "yellow",     Slot::BrightYellow),
+    ("bright",  "blue",       Slot::BrightBlue),
+    ("bright",  "magenta",    Slot::BrightMagenta),
+    ("bright",  "cyan",       Slot::BrightCyan),
+    ("bright",  "white",      Slot::BrightWhite),
+    ("dim",     "black",      Slot::DimBlack),
+    ("dim",     "red",        Slot::DimRed),
+    ("dim",     "green",      Slot::DimGreen),
+    ("dim",     "yellow",     Slot::DimYellow),
+    ("dim",     "blue",       Slot::DimBlue),
+    ("dim",     "magenta",    Slot::DimMagenta),
+    ("dim",     "cyan",       Slot::DimCyan),
+    ("dim",     "white",      Slot::DimWhite),
+];
+
+// Read by `from_alacritty` but not written by `to_yaml`.
+#[cfg(feature = "yaml")]
+const ALACRITTY_IMPORT_KEYS: [(&str, &str, Slot); 2] = [
+    ("cursor",  "cursor",     Slot::Cursor),
+    ("cursor",  "text",       Slot::CursorText),
+];
+
+const SVG_SWATCH_WIDTH: usize = 96;
+const SVG_SWATCH_HEIGHT: usize = 48;
+const SVG_LABEL_HEIGHT: usize = 16;
+const SVG_COLUMNS: usize = 8;
+
+// What `ColorScheme::iter` yields for unset slots.
+static UNSET: Color = Color { red: 0, green: 0, blue: 0, alpha: None };
+
+// How much lighter derived bright colors are than the normal ones.
+const BRIGHT_LIGHTEN: f32 = 0.2;
+
+// How much darker derived dim colors are than the normal ones.
+const DIM_DARKEN: f32 = 0.2;
+
+// Declares every color a scheme holds once: its `ColorScheme` field, its
+// `Slot`, named after the field, and the accessors between the two.
+// The `named` ones are the 18 that `iter` yields, `extra` holds the rest.
+macro_rules! color_slots {
+    (
+        named { $($named_field:ident => $named_slot:ident,)* }
+        extra { $($field:ident => $slot:ident,)* }
+    ) => {
+        #[derive(Debug, Clone, Copy, PartialEq)]
+        enum Slot {
+            $($named_slot,)*
+            $($slot,)*
+        }
+
+        // The slots of `iter`, in declaration order.
+        const NAMED_SLOTS: &[Slot] = &[$(Slot::$named_slot,)*];
+
+        // Every slot in declaration order, `NAMED_SLOTS` first.
+        const SLOTS: &[Slot] = &[$(Slot::$named_slot,)* $(Slot::$slot,)*];
+
+        impl Slot {
+            fn name(self) -> &'static str {
+                match self {
+                    $(Slot::$named_slot => stringify!($named_field),)*
+                    $(Slot::$slot => stringify!($field),)*
+                }
+            }
+        }
+
+        #[derive(Debug, Default, Clone, PartialEq)]
+        pub struct ColorScheme {
+            $($named_field: Option<Color>,)*
+            $($field: Option<Color>,)*
+
+            // Palette entries 16-255 by index, sorted; only emitted by
+            // `to_yaml_extended`.
+            indexed_colors: Vec<(u8, Color)>,
+        }
+
+        impl ColorScheme {
+            fn slot(&self, slot: Slot) -> &Option<Color> {
+                match slot {
+                    $(Slot::$named_slot => &self.$named_field,)*
+                    $(Slot::$slot => &self.$field,)*
+                }
+            }
+
+            fn slot_mut(&mut self, slot: Slot) -> &mut Option<Color> {
+                match slot {
+                    $(Slot::$named_slot => &mut self.$named_field,)*
+                    $(Slot::$slot => &mut self.$field,)*
+                }
+            }
+
+            // Same order as `SLOTS`.
+            fn colors_mut(&mut self) -> Vec<&mut Option<Color>> {
+                vec![$(&mut self.$named_field,)* $(&mut self.$field,)*]
+            }
+        }
+    };
+}
+
+color_slots! {
+    named {
+        foreground => Foreground,
+        background => Background,
+
+        black   => Black,
+        red     => Red,
+        green   => Green,
+        yellow  => Yellow,
+        blue    => Blue,
+        magenta => Magenta,
+        cyan    => Cyan,
+        white   => White,
+
+        bright_black   => BrightBlack,
+        bright_red     => BrightRed,
+        bright_green   => BrightGreen,
+        bright_yellow  => BrightYellow,
+        bright_blue    => BrightBlue,
+        bright_magenta => BrightMagenta,
+        bright_cyan    => BrightCyan,
+        bright_white   => BrightWhite,
+    }
+
+    extra {
+        cursor => Cursor,
+
+        // iTerm-only UI colors, only emitted by `to_iterm`.
+        badge       => Badge,
+        tab         => Tab,
+        bold        => Bold,
+        cursor_text => CursorText,
+
+        // Faint (SGR 2) colors, only emitted by `to_yaml`.
+        dim_black   => DimBlack,
+        dim_red     => DimRed,
+        dim_green   => DimGreen,
+        dim_yellow  => DimYellow,
+        dim_blue    => DimBlue,
+        dim_magenta => DimMagenta,
+        dim_cyan    => DimCyan,
+        dim_white   => DimWhite,
+    }
 }
 
 impl ColorScheme {
-    pub fn from_minttyrc(content: &str) -> Self {
+    fn slots(&self) -> Vec<(&'static str, &Option<Color>)> {
+        NAMED_SLOTS.iter().map(|&slot| (slot.name(), self.slot(slot))).collect()
+    }
+
+    /// The 18 color slots by name, in this order: `foreground`,
+    /// `background`, `black` through `white` and `bright_black` through
+    /// `bright_white`, each run in ANSI order. Unset slots yield black, as
+    /// the exporters write them. Cursor and the iTerm UI colors aren't
+    /// included.
+    pub fn iter(&self) -> impl Iterator<Item = (&'static str, &Color)> {
+        self.slots()
+            .into_iter()
+            .map(|(name, color)| (name, color.as_ref().unwrap_or(&UNSET)))
+    }
+
+    /// The slots from `iter` whose colors differ, with ours first. Use `==`
+    /// to also compare cursor, iTerm UI and dim colors, and set vs. unset.
+    pub fn diff(&self, other: &ColorScheme) -> Vec<(&'static str, Color, Color)> {
+        self.iter()
+            .zip(other.iter())
+            .filter(|&((_, ours), (_, theirs))| ours != theirs)
+            .map(|((name, ours), (_, theirs))| (name, *ours, *theirs))
+            .collect()
+    }
+
+    // Same order as `SLOTS`.
+    fn colors(&self) -> Vec<&Option<Color>> {
+        SLOTS.iter().map(|&slot| self.slot(slot)).collect()
+    }
+
+    // The slot `key` maps to in one of the format tables.
+    fn keyed_slot_mut(&mut self, keys: &[(&str, Slot)], key: &str) -> Option<&mut Option<Color>> {
+        let &(_, slot) = keys.iter().find(|&&(k, _)| k == key)?;
+        Some(self.slot_mut(slot))
+    }
+
+    // The colors a format table has keys for, in its order.
+    fn keyed_colors(&self, keys: &[(&'static str, Slot)]) -> Vec<(&'static str, &Option<Color>)> {
+        keys.iter().map(|&(key, slot)| (key, self.slot(slot))).collect()
+    }
+
+    /// Layers `other` on top: every color `other` sets replaces ours, the
+    /// ones it leaves unset are kept. Handy for applying a partial file,
+    /// e.g. a minttyrc that only overrides a couple of colors.
+    pub fn merge(&mut self, other: &ColorScheme) {
+        for (color, other_color) in self.colors_mut().into_iter().zip(other.colors()) {
+            if other_color.is_some() {
+                *color = *other_color;
+            }
+        }
+        for &(index, color) in &other.indexed_colors {
+            self.insert_indexed_color(index, color);
+        }
+    }
+
+    /// Whether the background `is_dark`. An unset background counts as
+    /// black, as the exporters write it.
+    pub fn is_dark_theme(&self) -> bool {
+        self.background.unwrap_or_default().is_dark()
+    }
+
+    // For strict parsing: every one of `NAMED_SLOTS` must be set.
+    // Missing ones are reported by their key in `keys`, the format's table.
+    fn check_complete(&self, keys: &[(&'static str, Slot)]) -> Result<(), ColorError> {
+        let required: Vec<_> = keys.iter().cloned().filter(|&(_, slot)| NAMED_SLOTS.contains(&slot)).collect();
+        let missing: Vec<String> = self.keyed_colors(&required)
+            .into_iter()
+            .filter(|&(_, color)| color.is_none())
+            .map(|(name, _)| name.to_string())
+            .collect();
+        if missing.is_empty() { Ok(()) } else { Err(ColorError::MissingKeys(missing)) }
+    }
+
+    /// Parses `data` as `hint`, or as whatever `ColorSchemeFormat::detect`
+    /// makes of it when no hint is given. With the `gzip` feature,
+    /// gzip-compressed data is decompressed first.
+    pub fn from_bytes(data: &[u8], hint: Option<ColorSchemeFormat>) -> Result<Self, ColorError> {
+        let data = decompress(data)?;
+        let content = str::from_utf8(&data).map_err(ColorError::Utf8)?;
+        let format = hint
+            .or_else(|| ColorSchemeFormat::detect(content))
+            .ok_or(ColorError::UnknownFormat)?;
+        match format {
+            ColorSchemeFormat::ITerm       => ColorScheme::from_iterm(content),
+            ColorSchemeFormat::Mintty      => ColorScheme::from_minttyrc(content),
+            ColorSchemeFormat::VSCode      => ColorScheme::from_vscode(content),
+            ColorSchemeFormat::Foot        => ColorScheme::from_foot(content),
+            ColorSchemeFormat::Json        => ColorScheme::from_json(content),
+            ColorSchemeFormat::TerminalApp => ColorScheme::from_terminal_app(content),
+        }
+    }
+
+    pub fn from_minttyrc(content: &str) -> Result<Self, ColorError> {
+        ColorScheme::from_minttyrc_with(content, ParseOptions::default())
+    }
+
+    pub fn from_minttyrc_with(content: &str, options: ParseOptions) -> Result<Self, ColorError> {
+        ColorScheme::from_minttyrc_with_warnings(content, options).map(|(scheme, _)| scheme)
+    }
+
+    /// Like `from_minttyrc_with`, also reporting problems that don't stop
+    /// parsing. A repeated key keeps its last value.
+    pub fn from_minttyrc_with_warnings(
+        content: &str,
+        options: ParseOptions,
+    ) -> Result<(Self, Vec<ValidationWarning>), ColorError> {
         let mut scheme = ColorScheme::default();
-        for line in content.lines() {
-            let components: Vec<&str> = line.split("=").collect();
+        let mut warnings = Vec::new();
+        let mut seen = HashSet::new();
+        for (index, raw_line) in content.lines().enumerate() {
+            let invalid = |cause: ColorError| ColorError::InvalidLine {
+                line: index + 1,
+                text: raw_line.trim().to_string(),
+                cause: Box::new(cause),
+            };
+            // `lines` already drops the `\r` of CRLF endings.
+            let line = match raw_line.find('#') {
+                Some(comment) => &raw_line[..comment],
+                None          => raw_line,
+            }.trim();
+            if line.is_empty() {
+                continue;
+            }
+            let components: Vec<&str> = line.splitn(2, '=').collect();
             if components.len() != 2 {
-                panic!("Invalid line: {}", line);
-            }
-            let name = components[0];
-            let color = Color::from_string(components[1]).unwrap();
-            match name {
-                "ForegroundColour" => scheme.foreground     = color,
-                "BackgroundColour" => scheme.background     = color,
-                "Black"            => scheme.black          = color,
-                "Red"              => scheme.red            = color,
-                "Green"            => scheme.green          = color,
-                "Yellow"           => scheme.yellow         = color,
-                "Blue"             => scheme.blue           = color,
-                "Magenta"          => scheme.magenta        = color,
-                "Cyan"             => scheme.cyan           = color,
-                "White"            => scheme.white          = color,
-                "BoldRed"          => scheme.bright_red     = color,
-                "BoldBlack"        => scheme.bright_black   = color,
-                "BoldGreen"        => scheme.bright_green   = color,
-                "BoldYellow"       => scheme.bright_yellow  = color,
-                "BoldBlue"         => scheme.bright_blue    = color,
-                "BoldMagenta"      => scheme.bright_magenta = color,
-                "BoldCyan"         => scheme.bright_cyan    = color,
-                "BoldWhite"        => scheme.bright_white   = color,
-                _                  => panic!("Invalid color name: {}", name),
+                return Err(invalid(ColorError::InvalidFormat));
+            }
+            let name = components[0].trim();
+            let target = match scheme.keyed_slot_mut(&MINTTY_KEYS, name) {
+                Some(target)           => target,
+                None if options.strict => return Err(invalid(ColorError::InvalidFormat)),
+                None                   => continue,
+            };
+            *target = Some(Color::from_string(components[1].trim()).map_err(invalid)?);
+            if !seen.insert(name) {
+                warnings.push(ValidationWarning::DuplicateKey(name.to_string()));
             }
         }
-        scheme
+        if options.strict {
+            scheme.check_complete(&MINTTY_KEYS)?;
+        }
+        Ok((scheme, warnings))
+    }
+
+    pub fn from_iterm(content: &str) -> Result<Self, ColorError> {
+        ColorScheme::from_iterm_with(content, ParseOptions::default())
+    }
+
+    pub fn from_iterm_with(content: &str, options: ParseOptions) -> Result<Self, ColorError> {
+        ColorScheme::from_iterm_with_warnings(content, options).map(|(scheme, _)| scheme)
     }
 
-    pub fn from_iterm(content: &str) -> Self {
+    /// Like `from_iterm_with`, also reporting problems that don't stop
+    /// parsing. A repeated key keeps its last value.
+    pub fn from_iterm_with_warnings(
+        content: &str,
+        options: ParseOptions,
+    ) -> Result<(Self, Vec<ValidationWarning>), ColorError> {
+        let root: Element = content.parse().map_err(|_| ColorError::InvalidFormat)?;
+        let root_dict = root.get_child("dict", None).ok_or(ColorError::InvalidFormat)?;
+        ColorScheme::from_iterm_dict(root_dict, options)
+    }
+
+    /// Every scheme in a bundle plist whose top-level keys are scheme names,
+    /// each holding what a single `.itermcolors` would, with its name. A
+    /// single `.itermcolors` is rejected rather than read as a bundle.
+    pub fn all_from_iterm(content: &str) -> Result<Vec<(String, ColorScheme)>, ColorError> {
+        let root: Element = content.parse().map_err(|_| ColorError::InvalidFormat)?;
+        let root_dict = root.get_child("dict", None).ok_or(ColorError::InvalidFormat)?;
+
+        let mut schemes = Vec::new();
+        for (name, dict) in dict_entries(root_dict) {
+            if dict.name != "dict" || ITERM_KEYS.iter().any(|&(key, _)| key == name) {
+                return Err(ColorError::InvalidFormat);
+            }
+            let (scheme, _) = ColorScheme::from_iterm_dict(dict, ParseOptions::default())?;
+            schemes.push((name, scheme));
+        }
+        Ok(schemes)
+    }
+
+    fn from_iterm_dict(
+        dict: &Element,
+        options: ParseOptions,
+    ) -> Result<(Self, Vec<ValidationWarning>), ColorError> {
         let mut scheme = ColorScheme::default();
+        let mut warnings = Vec::new();
+        let mut seen = HashSet::new();
 
-        let root: Element = content.parse().unwrap();
-        let root_dict: &Element = &root.get_children("dict", None).nth(0).unwrap();
-
-        let keys = root_dict.get_children("key", None);
-        let values = root_dict.get_children("dict", None);
-        for (key, value) in keys.zip(values) {
-            let color_name = extract_text(key);
-            let color_keys = value.get_children("key", None);
-            let color_values = value.get_children("real", None);
-
-            let mut color = Color::default();
-            for (color_key, color_value) in color_keys.zip(color_values) {
-                let component_name = extract_text(color_key);
-                let real_value: f32 = extract_text(color_value).parse().unwrap();
-                let int_value = (real_value * 255.0) as u8;
-                match component_name {
-                    "Red Component"   => color.red   = int_value,
-                    "Green Component" => color.green = int_value,
-                    "Blue Component"  => color.blue  = int_value,
-                    _                 => panic!("Invalid color component name: {}", component_name),
+        for (color_name, value) in dict_entries(dict) {
+            if value.name != "dict" {
+                continue;
+            }
+            if !seen.insert(color_name.clone()) {
+                warnings.push(ValidationWarning::DuplicateKey(color_name.clone()));
+            }
+            let invalid = |cause: ColorError| ColorError::InvalidKey {
+                key: color_name.clone(),
+                cause: Box::new(cause),
+            };
+
+            let (mut red, mut green, mut blue, mut alpha) = (None, None, None, None);
+            // Newer exports add a `Color Space` string (`sRGB`, `Calibrated`,
+            // ...); the components are taken as they are either way. They may
+            // also add an `Alpha Component`, which an opaque 1 is the same as
+            // leaving out.
+            for (component_name, color_value) in dict_entries(value) {
+                if color_value.name != "real" {
+                    continue;
+                }
+                let real_value: f32 = color_value.content_str().trim().parse()
+                    .map_err(|_| invalid(ColorError::InvalidFormat))?;
+                // Some exports go slightly past 1.0.
+                let int_value = (real_value * 255.0).round().clamp(0.0, 255.0) as u8;
+                match component_name.as_str() {
+                    "Red Component"   => red   = Some(int_value),
+                    "Green Component" => green = Some(int_value),
+                    "Blue Component"  => blue  = Some(int_value),
+                    "Alpha Component" => alpha = Some(int_value),
+                    _                 => return Err(invalid(ColorError::InvalidFormat)),
                 };
             }
+            let color = match (red, green, blue) {
+                (Some(red), Some(green), Some(blue)) => Color { red, green, blue, alpha }.normalize(),
+                _ => {
+                    let missing = [("Red Component", red), ("Green Component", green), ("Blue Component", blue)]
+                        .iter()
+                        .filter(|&&(_, value)| value.is_none())
+                        .map(|&(name, _)| name.to_string())
+                        .collect();
+                    return Err(invalid(ColorError::MissingKeys(missing)));
+                },
+            };
 
-            match color_name {
-                "Ansi 0 Color"     => scheme.black          = color,
-                "Ansi 1 Color"     => scheme.red            = color,
-                "Ansi 2 Color"     => scheme.green          = color,
-                "Ansi 3 Color"     => scheme.yellow         = color,
-                "Ansi 4 Color"     => scheme.blue           = color,
-                "Ansi 5 Color"     => scheme.magenta        = color,
-                "Ansi 6 Color"     => scheme.cyan           = color,
-                "Ansi 7 Color"     => scheme.white          = color,
-                "Ansi 8 Color"     => scheme.bright_black   = color,
-                "Ansi 9 Color"     => scheme.bright_red     = color,
-                "Ansi 10 Color"    => scheme.bright_green   = color,
-                "Ansi 11 Color"    => scheme.bright_yellow  = color,
-                "Ansi 12 Color"    => scheme.bright_blue    = color,
-                "Ansi 13 Color"    => scheme.bright_magenta = color,
-                "Ansi 14 Color"    => scheme.bright_cyan    = color,
-                "Ansi 15 Color"    => scheme.bright_white   = color,
-                "Background Color" => scheme.background     = color,
-                "Foreground Color" => scheme.foreground     = color,
-                _                  => (),
+            match scheme.keyed_slot_mut(&ITERM_KEYS, &color_name) {
+                Some(target) => *target = Some(color),
+                None if options.strict && !ITERM_UNUSED_KEYS.contains(&color_name.as_str()) => {
+                    return Err(invalid(ColorError::InvalidFormat));
+                },
+                None         => (),
             }
         }
 
+        if options.strict {
+            scheme.check_complete(&ITERM_KEYS)?;
+        }
+        Ok((scheme, warnings))
+    }
+
+    /// Terminal colors from VS Code's `workbench.colorCustomizations`, given
+    /// either the whole `settings.json` or just that object. Comments and
+    /// trailing commas aren't supported, and settings that set none of the
+    /// terminal colors are `InvalidFormat`.
+    pub fn from_vscode(content: &str) -> Result<Self, ColorError> {
+        let root = json::parse(content).map_err(|_| ColorError::InvalidFormat)?;
+        let customizations = if root["workbench.colorCustomizations"].is_object() {
+            &root["workbench.colorCustomizations"]
+        } else {
+            &root
+        };
+        if !customizations.is_object() {
+            return Err(ColorError::InvalidFormat);
+        }
+
+        let mut scheme = ColorScheme::default();
+        for (key, value) in customizations.entries() {
+            let target = match scheme.keyed_slot_mut(&VSCODE_KEYS, key) {
+                Some(target) => target,
+                None         => continue,
+            };
+            let hex = value.as_str().ok_or(ColorError::InvalidFormat)?;
+            *target = Some(Color::from_hex(hex)?);
+        }
+        // Settings without any `terminal.*` colors would convert to all black.
+        if scheme == ColorScheme::default() {
+            return Err(ColorError::InvalidFormat);
+        }
+        Ok(scheme)
+    }
+
+    /// The layout written by `to_json`, or the same with a flat `palette`
+    /// array of 8 or 16 colors in ANSI order in place of `normal` and
+    /// `bright`. With 8, the brights are derived by `fill_missing_brights`.
+    pub fn from_json(content: &str) -> Result<Self, ColorError> {
+        let root = json::parse(content).map_err(|_| ColorError::InvalidFormat)?;
+        if !root.is_object() {
+            return Err(ColorError::InvalidFormat);
+        }
+        let parse = |value: &json::JsonValue| {
+            if value.is_null() {
+                return Ok(None);
+            }
+            let hex = value.as_str().ok_or(ColorError::InvalidFormat)?;
+            Color::from_hex(hex).map(Some)
+        };
+
+        let mut scheme = ColorScheme {
+            foreground: parse(&root["foreground"])?,
+            background: parse(&root["background"])?,
+            cursor: parse(&root["cursor"])?,
+            ..ColorScheme::default()
+        };
+
+        if root.has_key("palette") {
+            let palette = &root["palette"];
+            if !palette.is_array() || (palette.len() != 8 && palette.len() != 16) {
+                return Err(ColorError::InvalidFormat);
+            }
+            let normal = ANSI_SLOTS.iter().map(|&(normal, _, _)| normal);
+            let bright = ANSI_SLOTS.iter().map(|&(_, bright, _)| bright);
+            for (slot, value) in normal.chain(bright).zip(palette.members()) {
+                *scheme.slot_mut(slot) = parse(value)?;
+            }
+            scheme.fill_missing_brights();
+        } else {
+            for &(normal, bright, _) in &ANSI_SLOTS {
+                *scheme.slot_mut(normal) = parse(&root["normal"][normal.name()])?;
+                *scheme.slot_mut(bright) = parse(&root["bright"][normal.name()])?;
+            }
+        }
+        Ok(scheme)
+    }
+
+    /// A single Terminal.app profile, as saved in a `.terminal` file, or the
+    /// `Default Window Settings` profile of `defaults export
+    /// com.apple.Terminal -`; see `from_terminal_app_all` for the others.
+    pub fn from_terminal_app(content: &str) -> Result<Self, ColorError> {
+        let root: Element = content.parse().map_err(|_| ColorError::InvalidFormat)?;
+        let root_dict = root.get_child("dict", None).ok_or(ColorError::InvalidFormat)?;
+        let entries = dict_entries(root_dict);
+        let find = |name: &str| entries.iter().find(|(key, _)| key == name).map(|&(_, value)| value);
+        let window_settings = match find("Window Settings") {
+            Some(window_settings) => window_settings,
+            None                  => return ColorScheme::from_terminal_app_profile(root_dict),
+        };
+        let default_name = find("Default Window Settings")
+            .map(|value| value.content_str())
+            .ok_or(ColorError::InvalidFormat)?;
+        let profile = dict_entries(window_settings)
+            .into_iter()
+            .find(|(name, _)| *name == default_name)
+            .map(|(_, profile)| profile)
+            .ok_or(ColorError::InvalidFormat)?;
+        ColorScheme::from_terminal_app_profile(profile)
+    }
+
+    /// Every profile under `Window Settings` in the output of
+    /// `defaults export com.apple.Terminal -`, with its name.
+    pub fn from_terminal_app_all(content: &str) -> Result<Vec<(String, ColorScheme)>, ColorError> {
+        let root: Element = content.parse().map_err(|_| ColorError::InvalidFormat)?;
+        let root_dict = root.get_child("dict", None).ok_or(ColorError::InvalidFormat)?;
+        let window_settings = dict_entries(root_dict)
+            .into_iter()
+            .find(|(key, _)| key == "Window Settings")
+            .map(|(_, value)| value)
+            .ok_or(ColorError::InvalidFormat)?;
+
+        let mut schemes = Vec::new();
+        for (name, profile) in dict_entries(window_settings) {
+            if profile.name != "dict" {
+                return Err(ColorError::InvalidFormat);
+            }
+            schemes.push((name, ColorScheme::from_terminal_app_profile(profile)?));
+        }
+        Ok(schemes)
+    }
+
+    fn from_terminal_app_profile(profile: &Element) -> Result<Self, ColorError> {
+        let mut scheme = ColorScheme::default();
+        for (key, value) in dict_entries(profile) {
+            let target = match scheme.keyed_slot_mut(&TERMINAL_APP_KEYS, &key) {
+                Some(target) => target,
+                None         => continue,
+            };
+            *target = Some(decode_nscolor(value)?);
+        }
+        // Anything else, e.g. the whole of a defaults export, isn't a profile.
+        if scheme == ColorScheme::default() {
+            return Err(ColorError::InvalidFormat);
+        }
+        Ok(scheme)
+    }
+
+    /// The `[colors]` section of a foot.ini. All of `regular0..7` and
+    /// `bright0..7` are required; the ones missing are reported together.
+    pub fn from_foot(content: &str) -> Result<Self, ColorError> {
+        let mut scheme = ColorScheme::default();
+        let mut in_colors = false;
+        for line in content.lines() {
+            let line = line.split('#').next().unwrap().trim();
+            if line.starts_with('[') {
+                in_colors = line == "[colors]";
+                continue;
+            }
+            if !in_colors || line.is_empty() {
+                continue;
+            }
+
+            let mut parts = line.splitn(2, '=');
+            let key = parts.next().unwrap().trim();
+            let value = parts.next().ok_or(ColorError::InvalidFormat)?.trim();
+            let target = match scheme.keyed_slot_mut(&FOOT_KEYS, key) {
+                Some(target) => target,
+                None         => continue,
+            };
+            if value.len() != 6 {
+                return Err(ColorError::InvalidFormat);
+            }
+            *target = Some(Color::from_hex(&format!("#{}", value))?);
+        }
+
+        let missing: Vec<String> = scheme.keyed_colors(&FOOT_KEYS[2..])
+            .into_iter()
+            .filter(|&(_, color)| color.is_none())
+            .map(|(key, _)| key.to_string())
+            .collect();
+        if !missing.is_empty() {
+            return Err(ColorError::MissingKeys(missing));
+        }
+        Ok(scheme)
+    }
+
+    /// The `colors` mapping of an Alacritty YAML config, wherever it's
+    /// nested, with everything else ignored. Reads the sections `to_yaml`
+    /// writes, the `indexed_colors` of `to_yaml_extended` and the `cursor`
+    /// section's `cursor` and `text` colors.
+    #[cfg(feature = "yaml")]
+    pub fn from_alacritty(content: &str) -> Result<Self, ColorError> {
+        let documents = YamlLoader::load_from_str(content).map_err(|_| ColorError::InvalidFormat)?;
+        let colors = documents.iter()
+            .filter_map(find_alacritty_colors)
+            .next()
+            .ok_or(ColorError::InvalidFormat)?;
+
+        let parse = |key: String, value: &Yaml| {
+            let invalid = |cause: ColorError| ColorError::InvalidKey { key: key.clone(), cause: Box::new(cause) };
+            // Unquoted `0x282a36` is a YAML integer.
+            let hex = match *value {
+                Yaml::String(ref hex) => hex.clone(),
+                Yaml::Integer(n) if (0..=0xffffff).contains(&n) => format!("#{:06x}", n),
+                _ => return Err(invalid(ColorError::InvalidFormat)),
+            };
+            Color::from_hex(&hex).map_err(invalid)
+        };
+
+        let mut scheme = ColorScheme::default();
+        for &(section, key, slot) in ALACRITTY_KEYS.iter().chain(&ALACRITTY_IMPORT_KEYS) {
+            let value = &colors[section][key];
+            if !value.is_badvalue() {
+                *scheme.slot_mut(slot) = Some(parse(format!("{}.{}", section, key), value)?);
+            }
+        }
+        if let Some(entries) = colors["indexed_colors"].as_vec() {
+            for entry in entries {
+                let index = entry["index"].as_i64()
+                    .filter(|&index| (0..=255).contains(&index))
+                    .ok_or(ColorError::InvalidFormat)?;
+                let color = parse(format!("indexed_colors.{}", index), &entry["color"])?;
+                scheme.set_indexed_color(index as u8, color)?;
+            }
+        }
+        Ok(scheme)
+    }
+
+    /// A generated starting point for a new theme. Red through cyan share
+    /// the accent's saturation and lightness, with the usual ANSI hues 60°
+    /// apart turned so the nearest one is the accent's. Black, white and the
+    /// foreground are `background` mixed towards white on a dark background
+    /// or black on a light one, the brights are lightened as by
+    /// `fill_missing_brights` and the cursor is the accent.
+    pub fn from_base(background: Color, accent: Color) -> ColorScheme {
+        let background = background.opaque();
+        let accent = accent.opaque();
+        let contrast = if background.is_dark() {
+            Color { red: 255, green: 255, blue: 255, alpha: None }
+        } else {
+            Color::default()
+        };
+        let (accent_hue, saturation, lightness) = accent.to_hsl();
+        let offset = (accent_hue + 30.0) % 60.0 - 30.0;
+        let hue = |base: f32| Some(Color::from_hsl(base + offset, saturation, lightness));
+
+        let mut scheme = ColorScheme {
+            foreground: Some(background.blend(&contrast, 0.85)),
+            background: Some(background),
+            black:      Some(background.blend(&contrast, 0.1)),
+            red:        hue(0.0),
+            green:      hue(120.0),
+            yellow:     hue(60.0),
+            blue:       hue(240.0),
+            magenta:    hue(300.0),
+            cyan:       hue(180.0),
+            white:      Some(background.blend(&contrast, 0.7)),
+            cursor:     Some(accent),
+            ..ColorScheme::default()
+        };
+        scheme.fill_missing_brights();
         scheme
     }
 
+    // Only the colors that are set, so patching doesn't clobber anything.
+    fn minttyrc_entries(&self) -> Vec<(&'static str, Color)> {
+        self.keyed_colors(&MINTTY_KEYS)
+            .into_iter()
+            .filter_map(|(key, color)| color.map(|color| (key, color)))
+            .collect()
+    }
+
+    pub fn to_minttyrc(&self) -> String {
+        self.minttyrc_entries()
+            .into_iter()
+            .map(|(key, color)| format!("{}={}\n", key, color.to_rgb_string()))
+            .collect()
+    }
+
+    // `ALACRITTY_KEYS` grouped by section, leaving out an empty `dim`.
+    fn alacritty_sections(&self) -> Vec<(&'static str, Vec<(&'static str, Color)>)> {
+        let mut sections: Vec<(&'static str, Vec<(&'static str, Color)>)> = Vec::new();
+        for &(section, key, slot) in &ALACRITTY_KEYS {
+            let color = match (section, *self.slot(slot)) {
+                ("dim", None) => continue,
+                (_, color)    => color.unwrap_or_default().opaque(),
+            };
+            match sections.last_mut() {
+                Some(&mut (last, ref mut entries)) if last == section => entries.push((key, color)),
+                _ => sections.push((section, vec![(key, color)])),
+            }
+        }
+        sections
+    }
+
     pub fn to_yaml(&self) -> String {
-        format!("colors:
-  # Default colors
-  primary:
-    background: '{}'
-    foreground: '{}'
-
-  # Normal colors
-  normal:
-    black:   '{}'
-    red:     '{}'
-    green:   '{}'
-    yellow:  '{}'
-    blue:    '{}'
-    magenta: '{}'
-    cyan:    '{}'
-    white:   '{}'
-
-  # Bright colors
-  bright:
-    black:   '{}'
-    red:     '{}'
-    green:   '{}'
-    yellow:  '{}'
-    blue:    '{}'
-    magenta: '{}'
-    cyan:    '{}'
-    white:   '{}'
+        self.to_yaml_with(YamlOptions::default())
+    }
+
+    pub fn to_yaml_with(&self, options: YamlOptions) -> String {
+        let indent = options.indent.max(1);
+        let section_indent = " ".repeat(indent);
+        let entry_indent = " ".repeat(2 * indent);
+        let sections: Vec<String> = self.alacritty_sections()
+            .into_iter()
+            .map(|(section, entries)| {
+                let title = match section {
+                    "primary" => "Default",
+                    "normal"  => "Normal",
+                    "bright"  => "Bright",
+                    _         => "Dim",
+                };
+                let mut yaml = String::new();
+                if options.comments {
+                    yaml.push_str(&format!("{}# {} colors\n", section_indent, title));
+                }
+                yaml.push_str(&format!("{}{}:\n", section_indent, section));
+                for (key, color) in entries {
+                    let key = format!("{}:", key);
+                    yaml.push_str(&format!("{}{:<8} '{}'\n", entry_indent, key, color.to_hex()));
+                }
+                yaml
+            })
+            .collect();
+        format!("colors:\n{}", sections.join("\n"))
+    }
+
+    /// `to_yaml` plus Alacritty's `indexed_colors` for the palette entries
+    /// from 16 up that are set, e.g. by `fill_256_palette`.
+    pub fn to_yaml_extended(&self) -> String {
+        self.to_yaml_extended_with(YamlOptions::default())
+    }
+
+    /// `to_yaml_with` plus `indexed_colors`, as in `to_yaml_extended`.
+    pub fn to_yaml_extended_with(&self, options: YamlOptions) -> String {
+        let mut yaml = self.to_yaml_with(options);
+        if !self.indexed_colors.is_empty() {
+            let indent = options.indent.max(1);
+            let section_indent = " ".repeat(indent);
+            let entry_indent = " ".repeat(2 * indent);
+            yaml.push_str(&format!("\n{}indexed_colors:\n", section_indent));
+            for &(index, color) in &self.indexed_colors {
+                yaml.push_str(&format!("{}- {{ index: {}, color: '{}' }}\n", entry_indent, index, color.opaque().to_hex()));
+            }
+        }
+        yaml
+    }
+
+    /// Copies the 16 ANSI colors from `other`, keeping everything else.
+    pub fn take_ansi_from(&mut self, other: &ColorScheme) {
+        for &(normal, bright, _) in &ANSI_SLOTS {
+            *self.slot_mut(normal) = *other.slot(normal);
+            *self.slot_mut(bright) = *other.slot(bright);
+        }
+    }
+
+    /// Copies the non-ANSI colors (foreground, background, cursor and the
+    /// iTerm UI colors) from `other`, keeping the ANSI palette.
+    pub fn take_ui_from(&mut self, other: &ColorScheme) {
+        let is_ansi = |slot: Slot| ANSI_SLOTS.iter().any(|&(normal, bright, dim)| {
+            slot == normal || slot == bright || slot == dim
+        });
+        for &slot in SLOTS.iter().filter(|&&slot| !is_ansi(slot)) {
+            *self.slot_mut(slot) = *other.slot(slot);
+        }
+    }
+
+    fn map_colors<F: Fn(&Color) -> Color>(&self, f: F) -> ColorScheme {
+        let mut scheme = self.clone();
+        for color in scheme.colors_mut() {
+            *color = color.as_ref().map(|color| f(color).normalize());
+        }
+        for entry in &mut scheme.indexed_colors {
+            entry.1 = f(&entry.1).normalize();
+        }
+        scheme
+    }
+
+    /// Sets palette entry `index`. The first 16 are the ANSI colors, which
+    /// have their own slots, so an `index` below 16 is `InvalidFormat` and
+    /// leaves the scheme as it was.
+    pub fn set_indexed_color(&mut self, index: u8, color: Color) -> Result<(), ColorError> {
+        if index < 16 {
+            return Err(ColorError::InvalidFormat);
+        }
+        self.insert_indexed_color(index, color);
+        Ok(())
+    }
+
+    // `set_indexed_color` for an `index` already known to be 16 or above.
+    fn insert_indexed_color(&mut self, index: u8, color: Color) {
+        match self.indexed_colors.binary_search_by_key(&index, |&(i, _)| i) {
+            Ok(position)  => self.indexed_colors[position].1 = color,
+            Err(position) => self.indexed_colors.insert(position, (index, color)),
+        }
+    }
+
+    /// Sets every unset palette entry from 16 to 255 to xterm's default:
+    /// a 6x6x6 color cube followed by 24 grays.
+    pub fn fill_256_palette(&mut self) {
+        for index in 16..=255 {
+            if self.indexed_colors.binary_search_by_key(&index, |&(i, _)| i).is_err() {
+                self.insert_indexed_color(index, xterm_indexed_color(index));
+            }
+        }
+    }
+
+    /// All 256 palette entries: the 16 ANSI colors (black when unset), then
+    /// the indexed colors, with xterm's defaults for the unset ones.
+    pub fn to_256_palette(&self) -> Vec<Color> {
+        let mut palette: Vec<Color> = self.iter().skip(2).map(|(_, color)| *color).collect();
+        let mut indexed = self.indexed_colors.iter().peekable();
+        for index in 16..=255 {
+            match indexed.peek() {
+                Some(&&(i, color)) if i == index => {
+                    palette.push(color);
+                    indexed.next();
+                },
+                _ => palette.push(xterm_indexed_color(index)),
+            }
+        }
+        palette
+    }
+
+    /// For inputs that only define the ANSI colors: an unset foreground
+    /// takes the scheme's `white` and an unset background its `black`.
+    pub fn infer_fg_bg(&mut self) {
+        if self.foreground.is_none() {
+            self.foreground = self.white;
+        }
+        if self.background.is_none() {
+            self.background = self.black;
+        }
+    }
+
+    /// Sets every unset dim color to a darkened copy of its normal
+    /// counterpart.
+    pub fn fill_dim_from_normal(&mut self) {
+        for &(normal, _, dim) in &ANSI_SLOTS {
+            let normal = *self.slot(normal);
+            let dim = self.slot_mut(dim);
+            if dim.is_none() {
+                *dim = normal.map(|color| color.darken(DIM_DARKEN));
+            }
+        }
+    }
+
+    /// Sets every unset ANSI color to xterm's default (see
+    /// `Color::from_ansi_name`), an unset foreground to white and an unset
+    /// background to black. Importers never do this on their own, so
+    /// callers that want incomplete input to fail can check for gaps instead.
+    pub fn fill_from_defaults(&mut self) {
+        for &slot in NAMED_SLOTS {
+            let color = self.slot_mut(slot);
+            if color.is_none() {
+                *color = match slot {
+                    Slot::Foreground => Some(Color { red: 255, green: 255, blue: 255, alpha: None }),
+                    Slot::Background => Some(Color::default()),
+                    _                => Color::from_ansi_name(slot.name()),
+                };
+            }
+        }
+    }
+
+    /// Sets every unset bright color to a lightened copy of its normal
+    /// counterpart, for sources that only define the first 8.
+    pub fn fill_missing_brights(&mut self) {
+        for &(normal, bright, _) in &ANSI_SLOTS {
+            let normal = *self.slot(normal);
+            let bright = self.slot_mut(bright);
+            if bright.is_none() {
+                *bright = normal.map(|color| color.lighten(BRIGHT_LIGHTEN));
+            }
+        }
+    }
+
+    /// For older iTerm exports without Ansi 8-15 that rely on Bold Color for
+    /// bright text: when no bright color is set, uses `bold` as bright white
+    /// and fills the rest with `fill_missing_brights`. Does nothing if any
+    /// bright color is set or `bold` isn't. `from_iterm` leaves this to the
+    /// caller so that the imported slots are only the ones the file set.
+    pub fn fill_brights_from_bold(&mut self) {
+        let no_brights = ANSI_SLOTS.iter().all(|&(_, bright, _)| self.slot(bright).is_none());
+        if no_brights && self.bold.is_some() {
+            self.bright_white = self.bold;
+            self.fill_missing_brights();
+        }
+    }
+
+    pub fn quantize(&self, bits_per_channel: u8) -> ColorScheme {
+        self.map_colors(|color| color.quantize(bits_per_channel))
+    }
+
+    /// Every color, the indexed ones included, through `Color::grayscale`.
+    pub fn desaturate(&self) -> ColorScheme {
+        self.map_colors(Color::grayscale)
+    }
+
+    /// Light/dark companion: flips the HSL lightness of foreground and
+    /// background, leaving the ANSI colors as they are.
+    pub fn invert_luminance(&self) -> ColorScheme {
+        let invert = |color: Color| {
+            let (_, _, lightness) = color.to_hsl();
+            color.with_lightness(1.0 - lightness)
+        };
+        let mut scheme = self.clone();
+        scheme.foreground = self.foreground.map(invert);
+        scheme.background = self.background.map(invert);
+        scheme
+    }
+
+    pub fn swap_fg_bg(&self) -> ColorScheme {
+        let mut scheme = self.clone();
+        scheme.foreground = self.background;
+        scheme.background = self.foreground;
+        scheme
+    }
+
+    /// Alacritty's TOML configuration, used since Alacritty 0.13.
+    pub fn to_toml(&self) -> String {
+        let sections: Vec<String> = self.alacritty_sections()
+            .into_iter()
+            .map(|(section, entries)| {
+                let mut toml = format!("[colors.{}]\n", section);
+                for (key, color) in entries {
+                    toml.push_str(&format!("{} = \"{}\"\n", key, color.to_hex()));
+                }
+                toml
+            })
+            .collect();
+        sections.join("\n")
+    }
+
+    /// A `[colors]` section for foot.ini.
+    pub fn to_foot(&self) -> String {
+        let mut foot = String::from("[colors]\n");
+        for (key, color) in self.keyed_colors(&FOOT_KEYS) {
+            let hex = color.unwrap_or_default().opaque().format_hex("");
+            foot.push_str(&format!("{}={}\n", key, hex));
+        }
+        foot
+    }
+
+    /// Color lines for a Ghostty config.
+    pub fn to_ghostty(&self) -> String {
+        let mut config = String::new();
+        for (i, (name, color)) in self.iter().enumerate() {
+            let hex = color.opaque().to_css_hex();
+            match i {
+                0 | 1 => config.push_str(&format!("{} = {}\n", name, hex)),
+                _     => config.push_str(&format!("palette = {}={}\n", i - 2, hex)),
+            }
+        }
+        if let Some(cursor) = self.cursor {
+            config.push_str(&format!("cursor-color = {}\n", cursor.opaque().to_css_hex()));
+        }
+        config
+    }
+
+    pub fn to_iterm(&self) -> String {
+        let mut plist = String::from("<?xml version=\"1.0\" encoding=\"UTF-8\"?>
+<!DOCTYPE plist PUBLIC \"-//Apple//DTD PLIST 1.0//EN\" \"http://www.apple.com/DTDs/PropertyList-1.0.dtd\">
+<plist version=\"1.0\">
+<dict>
+");
+        for (name, color) in self.keyed_colors(&ITERM_KEYS) {
+            if let Some(ref color) = *color {
+                plist.push_str(&iterm_color_entry(name, color));
+            }
+        }
+        plist.push_str("</dict>\n</plist>\n");
+        plist
+    }
+
+    /// An iTerm2 Dynamic Profile named `colortty`, to drop into
+    /// `~/Library/Application Support/iTerm2/DynamicProfiles/`.
+    pub fn to_iterm2_dynamic(&self) -> String {
+        self.to_iterm2_dynamic_named("colortty")
+    }
+
+    /// Like `to_iterm2_dynamic` with a custom profile name. The `Guid` is
+    /// derived from the name alone, so re-exporting under the same name,
+    /// edited or not, updates the existing profile instead of adding another.
+    pub fn to_iterm2_dynamic_named(&self, name: &str) -> String {
+        let entries = self.keyed_colors(&ITERM_KEYS);
+        let mut profile = json::JsonValue::new_object();
+        profile["Name"] = name.into();
+        for &(key, color) in &entries {
+            if let Some(ref color) = *color {
+                let mut components = json::JsonValue::new_object();
+                components["Red Component"] = (color.red as f64 / 255.0).into();
+                components["Green Component"] = (color.green as f64 / 255.0).into();
+                components["Blue Component"] = (color.blue as f64 / 255.0).into();
+                if let Some(alpha) = color.alpha {
+                    components["Alpha Component"] = (alpha as f64 / 255.0).into();
+                }
+                profile[key] = components;
+            }
+        }
+        profile["Guid"] = guid(name).into();
+
+        let mut root = json::JsonValue::new_object();
+        root["Profiles"] = json::JsonValue::Array(vec![profile]);
+        root.pretty(2)
+    }
+
+    pub fn to_roles(&self) -> SchemeRoles {
+        let color = |slot| self.slot(slot).unwrap_or_default();
+        SchemeRoles {
+            background: color(Slot::Background),
+            foreground: color(Slot::Foreground),
+            comment:    color(Slot::BrightBlack),
+            keyword:    color(Slot::Magenta),
+            keyword2:   color(Slot::Cyan),
+            string:     color(Slot::Green),
+            number:     color(Slot::Yellow),
+            literal:    color(Slot::Red),
+            function:   color(Slot::Blue),
+            operator:   color(Slot::Foreground),
+        }
+    }
+
+    /// Lite XL color theme, a Lua module assigning `core.style` entries from
+    /// the roles of `to_roles`.
+    pub fn to_lite_xl(&self) -> String {
+        let roles = self.to_roles();
+        format!("local style = require \"core.style\"
+local common = require \"core.common\"
+
+style.background = {{ common.color \"{background}\" }}
+style.background2 = {{ common.color \"{background}\" }}
+style.background3 = {{ common.color \"{background}\" }}
+style.text = {{ common.color \"{foreground}\" }}
+style.caret = {{ common.color \"{foreground}\" }}
+style.accent = {{ common.color \"{keyword}\" }}
+style.dim = {{ common.color \"{comment}\" }}
+style.line_number = {{ common.color \"{comment}\" }}
+style.line_number2 = {{ common.color \"{foreground}\" }}
+
+style.syntax[\"normal\"] = {{ common.color \"{foreground}\" }}
+style.syntax[\"symbol\"] = {{ common.color \"{foreground}\" }}
+style.syntax[\"comment\"] = {{ common.color \"{comment}\" }}
+style.syntax[\"keyword\"] = {{ common.color \"{keyword}\" }}
+style.syntax[\"keyword2\"] = {{ common.color \"{keyword2}\" }}
+style.syntax[\"number\"] = {{ common.color \"{number}\" }}
+style.syntax[\"literal\"] = {{ common.color \"{literal}\" }}
+style.syntax[\"string\"] = {{ common.color \"{string}\" }}
+style.syntax[\"operator\"] = {{ common.color \"{operator}\" }}
+style.syntax[\"function\"] = {{ common.color \"{function}\" }}
 ",
-            self.background.to_hex(),
-            self.foreground.to_hex(),
-            self.black.to_hex(),
-            self.red.to_hex(),
-            self.green.to_hex(),
-            self.yellow.to_hex(),
-            self.blue.to_hex(),
-            self.magenta.to_hex(),
-            self.cyan.to_hex(),
-            self.white.to_hex(),
-            self.bright_black.to_hex(),
-            self.bright_red.to_hex(),
-            self.bright_green.to_hex(),
-            self.bright_yellow.to_hex(),
-            self.bright_blue.to_hex(),
-            self.bright_magenta.to_hex(),
-            self.bright_cyan.to_hex(),
-            self.bright_white.to_hex(),
+            background = roles.background.to_css_hex(),
+            foreground = roles.foreground.to_css_hex(),
+            comment    = roles.comment.to_css_hex(),
+            keyword    = roles.keyword.to_css_hex(),
+            keyword2   = roles.keyword2.to_css_hex(),
+            string     = roles.string.to_css_hex(),
+            number     = roles.number.to_css_hex(),
+            literal    = roles.literal.to_css_hex(),
+            function   = roles.function.to_css_hex(),
+            operator   = roles.operator.to_css_hex(),
         )
     }
+
+    /// The canonical JSON without `meta`, on a single line; `from_json`
+    /// reads it back. `cursor` is included only when set.
+    pub fn to_json(&self) -> String {
+        self.to_json_with(JsonOptions::default())
+    }
+
+    /// colortty's own JSON layout, `{"foreground": "#rrggbb", "background":
+    /// ..., "normal": {"black": ..., ...}, "bright": {...}}`, plus `cursor`
+    /// when it's set. A color with an alpha channel, e.g. from an iTerm or VS
+    /// Code background, is written as `#rrggbbaa`; `from_json` reads both.
+    ///
+    /// With `with_meta`, a leading `meta` object adds:
+    ///
+    /// - `is_dark`: see `ColorScheme::is_dark_theme`
+    /// - `avg_luminance`: mean relative luminance of the 18 colors
+    /// - `min_contrast`: lowest contrast ratio against the background among
+    ///   the foreground and the normal and bright red through cyan, i.e. the
+    ///   colors expected to be readable as text
+    pub fn to_json_with(&self, options: JsonOptions) -> String {
+        let mut root = json::JsonValue::new_object();
+        if options.with_meta {
+            let luminances: Vec<f32> = self.iter()
+                .map(|(_, color)| color.relative_luminance())
+                .collect();
+            let avg_luminance = luminances.iter().sum::<f32>() / luminances.len() as f32;
+            let background = self.background.unwrap_or_default();
+            // Red through cyan, i.e. all but black and white.
+            let chromatic = &ANSI_SLOTS[1..7];
+            let text_colors = Some(Slot::Foreground).into_iter()
+                .chain(chromatic.iter().map(|&(normal, _, _)| normal))
+                .chain(chromatic.iter().map(|&(_, bright, _)| bright));
+            let min_contrast = text_colors
+                .map(|slot| self.slot(slot).unwrap_or_default().contrast_ratio(&background))
+                .fold(f32::INFINITY, f32::min);
+            let round = |v: f32| (v as f64 * 1000.0).round() / 1000.0;
+
+            let mut meta = json::JsonValue::new_object();
+            meta["is_dark"] = self.is_dark_theme().into();
+            meta["avg_luminance"] = round(avg_luminance).into();
+            meta["min_contrast"] = round(min_contrast).into();
+            root["meta"] = meta;
+        }
+
+        root["foreground"] = self.foreground.unwrap_or_default().to_css_hex().into();
+        root["background"] = self.background.unwrap_or_default().to_css_hex().into();
+        if let Some(cursor) = self.cursor {
+            root["cursor"] = cursor.to_css_hex().into();
+        }
+        let mut normal = json::JsonValue::new_object();
+        let mut bright = json::JsonValue::new_object();
+        for (name, color) in self.iter().skip(2) {
+            let hex = color.to_css_hex();
+            match name.strip_prefix("bright_") {
+                Some(base) => bright[base] = hex.into(),
+                None       => normal[name] = hex.into(),
+            }
+        }
+        root["normal"] = normal;
+        root["bright"] = bright;
+        root.dump()
+    }
+
+    /// A preview for a true-color terminal: each of the 18 colors as a block
+    /// followed by its name, with foreground and background, the normal and
+    /// the bright colors on a line each.
+    pub fn to_ansi_preview(&self) -> String {
+        let colors: Vec<(&'static str, &Color)> = self.iter().collect();
+        let mut preview = String::new();
+        for row in &[&colors[..2], &colors[2..10], &colors[10..]] {
+            let cells: Vec<String> = row.iter()
+                .map(|&(name, color)| format!(
+                    "\x1b[48;2;{};{};{}m  \x1b[0m {}", color.red, color.green, color.blue, name
+                ))
+                .collect();
+            preview.push_str(&cells.join("  "));
+            preview.push('\n');
+        }
+        preview
+    }
+
+    pub fn to_svg(&self) -> String {
+        self.to_svg_with(SvgOptions::default())
+    }
+
+    /// SVG preview with foreground and background on the first row, then the
+    /// normal and bright colors on a row each, drawn over the background.
+    pub fn to_svg_with(&self, options: SvgOptions) -> String {
+        let label_height = match options.labels {
+            LabelMode::Text => SVG_LABEL_HEIGHT,
+            _               => 0,
+        };
+        let cell_height = SVG_SWATCH_HEIGHT + label_height;
+        let width = SVG_COLUMNS * SVG_SWATCH_WIDTH;
+        let height = 3 * cell_height;
+
+        let mut svg = format!(
+            "<svg xmlns=\"http://www.w3.org/2000/svg\" width=\"{w}\" height=\"{h}\" viewBox=\"0 0 {w} {h}\">\n",
+            w = width,
+            h = height,
+        );
+        svg.push_str(&format!(
+            "<rect width=\"{}\" height=\"{}\" fill=\"{}\"/>\n",
+            width, height, self.background.unwrap_or_default().opaque().to_css_hex()
+        ));
+
+        for (i, (name, color)) in self.iter().enumerate() {
+            // The first two slots are foreground and background.
+            let (column, row) = if i < 2 {
+                (i, 0)
+            } else {
+                ((i - 2) % SVG_COLUMNS, 1 + (i - 2) / SVG_COLUMNS)
+            };
+            let x = column * SVG_SWATCH_WIDTH;
+            let y = row * cell_height;
+            let hex = color.opaque().to_css_hex();
+            // The slot name as id lets embedders and tests find a swatch.
+            let rect = format!(
+                "<rect id=\"{}\" x=\"{}\" y=\"{}\" width=\"{}\" height=\"{}\" fill=\"{}\"",
+                name, x, y, SVG_SWATCH_WIDTH, SVG_SWATCH_HEIGHT, hex
+            );
+            match options.labels {
+                LabelMode::Text => {
+                    svg.push_str(&format!("{}><title>{} {}</title></rect>\n", rect, name, hex));
+                    svg.push_str(&format!(
+                        "<text x=\"{}\" y=\"{}\" font-family=\"monospace\" font-size=\"10\" fill=\"{}\">{} {}</text>\n",
+                        x + 2, y + SVG_SWATCH_HEIGHT + 12,
+                        self.foreground.unwrap_or_default().opaque().to_css_hex(), name, hex
+                    ));
+                },
+                LabelMode::Tooltip => {
+                    svg.push_str(&format!("{}><title>{} {}</title></rect>\n", rect, name, hex));
+                },
+                LabelMode::None => {
+                    svg.push_str(&format!("{}/>\n", rect));
+                },
+            }
+        }
+
+        svg.push_str("</svg>\n");
+        svg
+    }
 }
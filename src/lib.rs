@@ -1 +1,2 @@
 pub mod color;
+mod bplist;
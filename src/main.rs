@@ -13,11 +13,11 @@ use hyper::client::Client;
 use hyper::net::HttpsConnector;
 use hyper::header::{UserAgent};
 use hyper_openssl::OpensslClient;
-use colortty::color::{ColorScheme, ColorSchemeFormat};
+use colortty::color::{ColorError, ColorScheme, ColorSchemeFormat};
 
 fn convert(args: Vec<String>) {
     let mut opts = Options::new();
-    opts.optopt("i", "input-format", "input format: 'iterm'|'mintty'", "INPUT_FORMAT");
+    opts.optopt("i", "input-format", "input format: 'iterm'|'mintty'|'vscode'|'foot'|'json'|'terminal-app'", "INPUT_FORMAT");
     let matches = match opts.parse(&args[2..]) {
         Ok(m)  => m,
         Err(f) => panic!(f.to_string()),
@@ -30,24 +30,24 @@ fn convert(args: Vec<String>) {
     let source = &matches.free[0];
     let input_format = matches.opt_str("i")
         .and_then(|s| ColorSchemeFormat::from_string(s.as_ref()))
-        .or_else(|| ColorSchemeFormat::from_filename(source.as_ref()))
-        .expect("Input format not specified and failed to guess");
+        .or_else(|| ColorSchemeFormat::from_filename(source.as_ref()));
 
-    let mut buffer = String::new();
+    let mut buffer = Vec::new();
     if source == "-" {
         io::stdin()
-            .read_to_string(&mut buffer)
+            .read_to_end(&mut buffer)
             .expect("Failed to get stdin");
     } else {
         File::open(source)
             .unwrap()
-            .read_to_string(&mut buffer)
+            .read_to_end(&mut buffer)
             .expect("Failed to read source");
     }
 
-    let scheme = match input_format {
-        ColorSchemeFormat::ITerm => ColorScheme::from_iterm(&buffer),
-        ColorSchemeFormat::Mintty => ColorScheme::from_minttyrc(&buffer),
+    let scheme = match ColorScheme::from_bytes(&buffer, input_format) {
+        Ok(scheme) => scheme,
+        Err(ColorError::UnknownFormat) => panic!("Input format not specified and failed to guess"),
+        Err(e) => panic!("Failed to parse source: {}", e),
     };
 
     print!("{}", scheme.to_yaml());
@@ -84,7 +84,7 @@ fn get(args: Vec<String>) {
     let url = format!("https://raw.githubusercontent.com/mbadolato/iTerm2-Color-Schemes/master/schemes/{}.itermcolors", name);
     let body = http_get(&url);
 
-    let scheme = ColorScheme::from_iterm(&body);
+    let scheme = ColorScheme::from_iterm(&body).expect("Failed to parse color scheme");
     print!("{}", scheme.to_yaml());
 }
 
@@ -1,4 +1,5 @@
 extern crate colortty;
+extern crate json;
 
 #[cfg(test)]
 mod color_tests {
@@ -9,7 +10,7 @@ mod color_tests {
         fn from_string_works() {
             assert_eq!(
                 Color::from_string("12,3,255").unwrap(),
-                Color { red: 12, green: 3, blue: 255 }
+                Color { red: 12, green: 3, blue: 255, alpha: None }
             );
         }
 
@@ -29,18 +30,293 @@ mod color_tests {
         #[test]
         fn to_hex() {
             assert_eq!(
-                Color { red: 123, green: 4, blue: 255 }.to_hex(),
+                Color { red: 123, green: 4, blue: 255, alpha: None }.to_hex(),
                 "0x7b04ff"
             );
         }
+
+        #[test]
+        fn to_hex_zero_pads() {
+            assert_eq!(
+                Color { red: 1, green: 2, blue: 3, alpha: None }.to_hex(),
+                "0x010203"
+            );
+        }
+
+        #[test]
+        fn to_hex_boundaries() {
+            assert_eq!(Color { red: 0, green: 0, blue: 0, alpha: None }.to_hex(), "0x000000");
+            assert_eq!(Color { red: 15, green: 15, blue: 15, alpha: None }.to_hex(), "0x0f0f0f");
+            assert_eq!(Color { red: 16, green: 16, blue: 16, alpha: None }.to_hex(), "0x101010");
+            assert_eq!(Color { red: 255, green: 255, blue: 255, alpha: None }.to_hex(), "0xffffff");
+        }
+
+        #[test]
+        fn from_hex_rgb() {
+            assert_eq!(
+                Color::from_hex("#1e1f28").unwrap(),
+                Color { red: 30, green: 31, blue: 40, alpha: None }
+            );
+            assert_eq!(
+                Color::from_hex("0x1e1f28").unwrap(),
+                Color { red: 30, green: 31, blue: 40, alpha: None }
+            );
+        }
+
+        #[test]
+        fn from_hex_rgba() {
+            assert_eq!(
+                Color::from_hex("#1e1f2880").unwrap(),
+                Color { red: 30, green: 31, blue: 40, alpha: Some(128) }
+            );
+        }
+
+        #[test]
+        fn from_hex_invalid() {
+            assert_eq!(Color::from_hex("#1e1f2"), Err(ColorError::InvalidFormat));
+            assert!(Color::from_hex("#1e1g28").is_err());
+        }
+
+        #[test]
+        fn from_hex_lengths() {
+            let cases = [
+                ("#1a2",      Some(Color { red: 0x11, green: 0xaa, blue: 0x22, alpha: None })),
+                ("#1a2f",     Some(Color { red: 0x11, green: 0xaa, blue: 0x22, alpha: Some(0xff) })),
+                ("0xfff",     Some(Color { red: 0xff, green: 0xff, blue: 0xff, alpha: None })),
+                ("#11aa22",   Some(Color { red: 0x11, green: 0xaa, blue: 0x22, alpha: None })),
+                ("#11aa2280", Some(Color { red: 0x11, green: 0xaa, blue: 0x22, alpha: Some(0x80) })),
+                ("",          None),
+                ("#",         None),
+                ("#1",        None),
+                ("#1a",       None),
+                ("#1a2b5",    None),
+                ("#1a2b3c4",  None),
+                ("#1a2b3c4d5", None),
+                ("#+f+f+f",   None),
+                ("+1+a+2",    None),
+            ];
+            for &(input, expected) in &cases {
+                match expected {
+                    Some(color) => assert_eq!(Color::from_hex(input), Ok(color), "{}", input),
+                    None        => assert_eq!(Color::from_hex(input), Err(ColorError::InvalidFormat), "{}", input),
+                }
+            }
+            assert!(Color::from_hex("#1g2").is_err());
+        }
+
+        #[test]
+        fn to_hex_alpha_suffix() {
+            let color = Color { red: 30, green: 31, blue: 40, alpha: Some(128) };
+            assert_eq!(color.to_hex(), "0x1e1f2880");
+            assert_eq!(color.to_css_hex(), "#1e1f2880");
+            assert_eq!(color.opaque().to_css_hex(), "#1e1f28");
+        }
+
+        #[test]
+        fn from_string_rgba() {
+            assert_eq!(
+                Color::from_string_rgba("12,3,255,128").unwrap(),
+                Color { red: 12, green: 3, blue: 255, alpha: Some(128) }
+            );
+            assert_eq!(
+                Color::from_string_rgba("12,3,255"),
+                Err(ColorError::InvalidFormat)
+            );
+        }
+
+        #[test]
+        fn from_components() {
+            let orange = Color { red: 255, green: 128, blue: 0, alpha: None };
+            assert_eq!(Color::from_components("255,128,0").unwrap(), orange);
+            assert_eq!(Color::from_components("255, 128, 0").unwrap(), orange);
+            assert_eq!(Color::from_components("255 128\t0").unwrap(), orange);
+            assert_eq!(Color::from_components("1.0 0.5 0").unwrap(), orange);
+            assert_eq!(Color::from_components("1 0 0").unwrap(), Color { red: 1, green: 0, blue: 0, alpha: None });
+            assert_eq!(Color::from_components("0.5 0.5"), Err(ColorError::InvalidFormat));
+            assert_eq!(Color::from_components("-0.5 0.5 0.5"), Err(ColorError::InvalidFormat));
+            assert!(Color::from_components("2.5 0 0").is_err());
+            // `from_string` stays strict.
+            assert!(Color::from_string("255 128 0").is_err());
+        }
+
+        #[test]
+        fn to_hsl() {
+            assert_eq!(Color { red: 255, green: 0, blue: 0, alpha: None }.to_hsl(), (0.0, 1.0, 0.5));
+            assert_eq!(Color { red: 0, green: 0, blue: 255, alpha: None }.to_hsl(), (240.0, 1.0, 0.5));
+            assert_eq!(Color { red: 255, green: 255, blue: 255, alpha: None }.to_hsl(), (0.0, 0.0, 1.0));
+        }
+
+        #[test]
+        fn hsl_round_trip() {
+            let samples = [
+                (0, 0, 0),
+                (30, 31, 40),
+                (255, 85, 85),
+                (80, 250, 123),
+                (189, 147, 249),
+                (255, 255, 255),
+            ];
+            for &(red, green, blue) in &samples {
+                let color = Color { red, green, blue, alpha: None };
+                let (hue, saturation, lightness) = color.to_hsl();
+                assert_eq!(Color::from_hsl(hue, saturation, lightness), color);
+            }
+        }
+
+        #[test]
+        fn quantize_to_8_bits_is_a_no_op() {
+            for value in 0..=255 {
+                let color = Color { red: value, green: 255 - value, blue: value / 2, alpha: None };
+                assert_eq!(color.quantize(8), color);
+            }
+        }
+
+        #[test]
+        fn quantize_to_1_bit() {
+            assert_eq!(
+                Color { red: 127, green: 128, blue: 255, alpha: Some(10) }.quantize(1),
+                Color { red: 0, green: 255, blue: 255, alpha: Some(10) }
+            );
+        }
+
+        #[test]
+        fn quantize_to_2_bits() {
+            assert_eq!(
+                Color { red: 40, green: 100, blue: 160, alpha: None }.quantize(2),
+                Color { red: 0, green: 85, blue: 170, alpha: None }
+            );
+        }
+
+        #[test]
+        fn blend() {
+            let a = Color { red: 0, green: 100, blue: 255, alpha: None };
+            let b = Color { red: 255, green: 0, blue: 0, alpha: None };
+            assert_eq!(a.blend(&b, 0.0), a);
+            assert_eq!(a.blend(&b, 1.0), b);
+            assert_eq!(a.blend(&b, 0.5), Color { red: 128, green: 50, blue: 128, alpha: None });
+            assert_eq!(a.blend(&b, -1.0), a);
+            assert_eq!(a.blend(&b, 2.0), b);
+        }
+
+        #[test]
+        fn blend_alpha() {
+            let a = Color { red: 0, green: 0, blue: 0, alpha: Some(0) };
+            let b = Color { red: 0, green: 0, blue: 0, alpha: None };
+            assert_eq!(a.blend(&b, 0.5).alpha, Some(128));
+        }
+
+        #[test]
+        fn relative_luminance() {
+            assert_eq!(Color { red: 0, green: 0, blue: 0, alpha: None }.relative_luminance(), 0.0);
+            assert_eq!(Color { red: 255, green: 255, blue: 255, alpha: None }.relative_luminance(), 1.0);
+        }
+
+        #[test]
+        fn contrast_ratio() {
+            let black = Color { red: 0, green: 0, blue: 0, alpha: None };
+            let white = Color { red: 255, green: 255, blue: 255, alpha: None };
+            assert!((black.contrast_ratio(&white) - 21.0).abs() < 0.001);
+            assert_eq!(white.contrast_ratio(&black), black.contrast_ratio(&white));
+            assert_eq!(white.contrast_ratio(&white), 1.0);
+        }
+
+        #[test]
+        fn normalize() {
+            let opaque = Color { red: 1, green: 2, blue: 3, alpha: None };
+            assert_eq!(Color { alpha: Some(255), ..opaque }.normalize(), opaque);
+            assert_eq!(opaque.normalize(), opaque);
+            let translucent = Color { alpha: Some(128), ..opaque };
+            assert_eq!(translucent.normalize(), translucent);
+            assert_eq!(Color { alpha: Some(255), ..opaque }.to_css_hex(), "#010203");
+            assert_eq!(translucent.to_hex(), "0x01020380");
+        }
+
+        #[test]
+        fn is_dark() {
+            assert!(Color::from_hex("#282a36").unwrap().is_dark());
+            assert!(!Color::from_hex("#fdf6e3").unwrap().is_dark());
+            // Mid gray is well under 0.5 once gamma is taken into account.
+            assert!(Color::from_hex("#808080").unwrap().is_dark());
+        }
+
+        #[test]
+        fn lighten() {
+            let red = Color { red: 255, green: 0, blue: 0, alpha: None };
+            assert_eq!(red.lighten(0.2), Color { red: 255, green: 102, blue: 102, alpha: None });
+            assert_eq!(red.lighten(1.0), Color { red: 255, green: 255, blue: 255, alpha: None });
+        }
+
+        #[test]
+        fn from_ansi_name() {
+            assert_eq!(Color::from_ansi_name("red"), Some(Color { red: 205, green: 0, blue: 0, alpha: None }));
+            assert_eq!(Color::from_ansi_name("bright_blue"), Some(Color { red: 92, green: 92, blue: 255, alpha: None }));
+            assert_eq!(Color::from_ansi_name("foreground"), None);
+            assert_eq!(Color::from_ansi_name("Red"), None);
+        }
+
+        #[test]
+        fn darken() {
+            let red = Color { red: 255, green: 102, blue: 102, alpha: None };
+            assert_eq!(red.darken(0.2), Color { red: 255, green: 0, blue: 0, alpha: None });
+            assert_eq!(red.darken(1.0), Color { red: 0, green: 0, blue: 0, alpha: None });
+        }
+
+        #[test]
+        fn to_rgb_string() {
+            assert_eq!(Color { red: 12, green: 3, blue: 255, alpha: None }.to_rgb_string(), "12,3,255");
+            assert_eq!(Color { red: 12, green: 3, blue: 255, alpha: Some(128) }.to_rgb_string(), "12,3,255");
+        }
+
+        #[test]
+        fn to_rgb_string_round_trip() {
+            let values = (0..=255u8).step_by(15).chain(vec![1, 9, 10, 99, 100, 254]);
+            for red in values.clone() {
+                for green in values.clone() {
+                    for blue in values.clone() {
+                        let color = Color { red, green, blue, alpha: None };
+                        assert_eq!(Color::from_string(&color.to_rgb_string()), Ok(color));
+                    }
+                }
+            }
+        }
+
+        #[test]
+        fn display() {
+            assert_eq!(Color { red: 30, green: 31, blue: 40, alpha: None }.to_string(), "#1e1f28");
+            assert_eq!(format!("{}", Color { red: 30, green: 31, blue: 40, alpha: Some(128) }), "#1e1f2880");
+        }
+
+        #[test]
+        fn from_str() {
+            let color = Color { red: 30, green: 31, blue: 40, alpha: None };
+            assert_eq!("#1e1f28".parse::<Color>(), Ok(color));
+            assert_eq!("0x1e1f28".parse::<Color>(), Ok(color));
+            assert_eq!("30,31,40".parse::<Color>(), Ok(color));
+            assert_eq!(color.to_string().parse::<Color>(), Ok(color));
+            assert_eq!("#1e1f2".parse::<Color>(), Err(ColorError::InvalidFormat));
+            assert_eq!("30,31".parse::<Color>(), Err(ColorError::InvalidFormat));
+        }
+
+        #[test]
+        fn grayscale() {
+            assert_eq!(
+                Color { red: 255, green: 85, blue: 85, alpha: Some(128) }.grayscale(),
+                Color { red: 136, green: 136, blue: 136, alpha: Some(128) }
+            );
+            assert_eq!(
+                Color { red: 255, green: 255, blue: 255, alpha: None }.grayscale(),
+                Color { red: 255, green: 255, blue: 255, alpha: None }
+            );
+        }
     }
 
     mod color_scheme {
-        use colortty::color::{ColorScheme};
-        use std::io::{Read};
-        use std::fs::File;
+        use colortty::color::{convert_dir, convert_reader, patch_minttyrc, Color, ColorError, ColorScheme, ColorSchemeFormat, JsonOptions, LabelMode, ParseOptions, SvgOptions, ValidationWarning, YamlOptions};
+        use std::io::{self, Cursor, Read, Write};
+        use std::env;
+        use std::fs::{self, File};
 
-        fn read_fixture(filename: &str) -> String {
+        pub fn read_fixture(filename: &str) -> String {
             let mut fixture= String::new();
             File::open(filename)
                 .unwrap()
@@ -49,6 +325,11 @@ mod color_tests {
             return fixture;
         }
 
+        // The color `iter` yields for the slot `name`.
+        fn slot_color(scheme: &ColorScheme, name: &str) -> Color {
+            scheme.iter().find(|&(slot, _)| slot == name).map(|(_, &color)| color).unwrap()
+        }
+
         #[test]
         fn convert_minttyrc() {
             let dracula_minttyrc = read_fixture("tests/fixtures/Dracula.minttyrc");
@@ -80,7 +361,7 @@ mod color_tests {
     cyan:    '0x9aedfe'
     white:   '0xe6e6e6'
 ".to_string();
-            let scheme = ColorScheme::from_minttyrc(&dracula_minttyrc);
+            let scheme = ColorScheme::from_minttyrc(&dracula_minttyrc).unwrap();
             assert_eq!(scheme.to_yaml(), dracula_alacritty);
         }
 
@@ -90,7 +371,7 @@ mod color_tests {
             let dracula_alacritty: String = "colors:
   # Default colors
   primary:
-    background: '0x1e1f28'
+    background: '0x1e1f29'
     foreground: '0xf8f8f2'
 
   # Normal colors
@@ -115,8 +396,1227 @@ mod color_tests {
     cyan:    '0x8be9fd'
     white:   '0xffffff'
 ".to_string();
-            let scheme = ColorScheme::from_iterm(&dracula_iterm);
+            let scheme = ColorScheme::from_iterm(&dracula_iterm).unwrap();
             assert_eq!(scheme.to_yaml(), dracula_alacritty);
         }
+
+        #[test]
+        fn convert_messy_minttyrc() {
+            let messy_minttyrc = read_fixture("tests/fixtures/Messy.minttyrc");
+            let dracula_minttyrc = read_fixture("tests/fixtures/Dracula.minttyrc");
+            assert!(messy_minttyrc.contains("\r\n"));
+            assert_eq!(
+                ColorScheme::from_minttyrc(&messy_minttyrc).unwrap().to_yaml(),
+                ColorScheme::from_minttyrc(&dracula_minttyrc).unwrap().to_yaml()
+            );
+        }
+
+        #[test]
+        fn convert_minttyrc_to_toml() {
+            let dracula_minttyrc = read_fixture("tests/fixtures/Dracula.minttyrc");
+            let dracula_alacritty: String = "[colors.primary]
+background = \"0x282a36\"
+foreground = \"0xf8f8f2\"
+
+[colors.normal]
+black = \"0x000000\"
+red = \"0xff5555\"
+green = \"0x50fa7b\"
+yellow = \"0xf1fa8c\"
+blue = \"0xcaa9fa\"
+magenta = \"0xff79c6\"
+cyan = \"0x8be9fd\"
+white = \"0xbfbfbf\"
+
+[colors.bright]
+black = \"0x282a35\"
+red = \"0xff6e67\"
+green = \"0x5af78e\"
+yellow = \"0xf4f99d\"
+blue = \"0xcaa9fa\"
+magenta = \"0xff92d0\"
+cyan = \"0x9aedfe\"
+white = \"0xe6e6e6\"
+".to_string();
+            let scheme = ColorScheme::from_minttyrc(&dracula_minttyrc).unwrap();
+            assert_eq!(scheme.to_toml(), dracula_alacritty);
+        }
+
+        #[test]
+        fn to_roles() {
+            let dracula_minttyrc = read_fixture("tests/fixtures/Dracula.minttyrc");
+            let roles = ColorScheme::from_minttyrc(&dracula_minttyrc).unwrap().to_roles();
+            assert_eq!(roles.background, Color { red: 40, green: 42, blue: 54, alpha: None });
+            assert_eq!(roles.foreground, Color { red: 248, green: 248, blue: 242, alpha: None });
+            assert_eq!(roles.comment, Color { red: 40, green: 42, blue: 53, alpha: None });
+            assert_eq!(roles.keyword, Color { red: 255, green: 121, blue: 198, alpha: None });
+            assert_eq!(roles.keyword2, Color { red: 139, green: 233, blue: 253, alpha: None });
+            assert_eq!(roles.string, Color { red: 80, green: 250, blue: 123, alpha: None });
+            assert_eq!(roles.number, Color { red: 241, green: 250, blue: 140, alpha: None });
+            assert_eq!(roles.literal, Color { red: 255, green: 85, blue: 85, alpha: None });
+            assert_eq!(roles.function, Color { red: 202, green: 169, blue: 250, alpha: None });
+            assert_eq!(roles.operator, roles.foreground);
+        }
+
+        #[test]
+        fn to_lite_xl() {
+            let dracula_minttyrc = read_fixture("tests/fixtures/Dracula.minttyrc");
+            let lua = ColorScheme::from_minttyrc(&dracula_minttyrc).unwrap().to_lite_xl();
+            assert!(lua.starts_with("local style = require \"core.style\"\n"));
+            assert!(lua.contains("style.background = { common.color \"#282a36\" }\n"));
+            assert!(lua.contains("style.syntax[\"comment\"] = { common.color \"#282a35\" }\n"));
+            assert!(lua.contains("style.syntax[\"string\"] = { common.color \"#50fa7b\" }\n"));
+        }
+
+        #[test]
+        fn iterm_round_trip_keeps_badge_and_tab_colors() {
+            let badge_iterm = read_fixture("tests/fixtures/DraculaBadge.itermcolors");
+            let exported = ColorScheme::from_iterm(&badge_iterm).unwrap().to_iterm();
+            assert!(exported.contains("\t<key>Badge Color</key>
+\t<dict>
+\t\t<key>Blue Component</key>
+\t\t<real>0.7764705882352941</real>
+\t\t<key>Green Component</key>
+\t\t<real>0.4745098039215686</real>
+\t\t<key>Red Component</key>
+\t\t<real>1</real>
+\t</dict>
+"));
+            assert!(exported.contains("\t<key>Tab Color</key>"));
+
+            let round_tripped = ColorScheme::from_iterm(&exported).unwrap();
+            assert_eq!(round_tripped.to_iterm(), exported);
+            assert_eq!(round_tripped.to_yaml(), ColorScheme::from_iterm(&badge_iterm).unwrap().to_yaml());
+        }
+
+        #[test]
+        fn to_iterm_omits_unset_badge_and_tab_colors() {
+            let dracula_iterm = read_fixture("tests/fixtures/Dracula.itermcolors");
+            let exported = ColorScheme::from_iterm(&dracula_iterm).unwrap().to_iterm();
+            assert!(!exported.contains("Badge Color"));
+            assert!(!exported.contains("Tab Color"));
+        }
+
+        #[test]
+        fn to_svg_without_labels() {
+            let dracula_minttyrc = read_fixture("tests/fixtures/Dracula.minttyrc");
+            let svg = ColorScheme::from_minttyrc(&dracula_minttyrc).unwrap()
+                .to_svg_with(SvgOptions { labels: LabelMode::None });
+            assert!(!svg.contains("<text"));
+            assert!(!svg.contains("<title>"));
+            // The backdrop plus one swatch per slot.
+            assert_eq!(svg.matches("<rect ").count(), 19);
+            assert!(svg.contains("fill=\"#ff5555\""));
+        }
+
+        #[test]
+        fn to_svg_labels() {
+            let dracula_minttyrc = read_fixture("tests/fixtures/Dracula.minttyrc");
+            let scheme = ColorScheme::from_minttyrc(&dracula_minttyrc).unwrap();
+
+            let text = scheme.to_svg();
+            assert_eq!(text.matches("<text ").count(), 18);
+            assert!(text.contains(">red #ff5555</text>"));
+            assert!(text.contains("<title>red #ff5555</title>"));
+
+            let tooltip = scheme.to_svg_with(SvgOptions { labels: LabelMode::Tooltip });
+            assert!(!tooltip.contains("<text"));
+            assert!(tooltip.contains("<title>red #ff5555</title>"));
+        }
+
+        #[test]
+        fn swap_fg_bg() {
+            let dracula_minttyrc = read_fixture("tests/fixtures/Dracula.minttyrc");
+            let yaml = ColorScheme::from_minttyrc(&dracula_minttyrc).unwrap().swap_fg_bg().to_yaml();
+            assert!(yaml.contains("    background: '0xf8f8f2'\n    foreground: '0x282a36'\n"));
+        }
+
+        #[test]
+        fn invert_luminance() {
+            let dracula_minttyrc = read_fixture("tests/fixtures/Dracula.minttyrc");
+            let scheme = ColorScheme::from_minttyrc(&dracula_minttyrc).unwrap();
+            let inverted = scheme.invert_luminance();
+
+            let (bg_hue, _, bg_lightness) = slot_color(&inverted, "background").to_hsl();
+            let (fg_hue, _, fg_lightness) = slot_color(&inverted, "foreground").to_hsl();
+            let (original_bg_hue, _, original_bg_lightness) = slot_color(&scheme, "background").to_hsl();
+            assert!((bg_lightness - (1.0 - original_bg_lightness)).abs() < 0.01);
+            assert!((bg_hue - original_bg_hue).abs() < 1.0);
+            assert!(bg_lightness > 0.5);
+            assert!(fg_lightness < 0.5);
+            assert!((fg_hue - 60.0).abs() < 1.0);
+
+            // ANSI colors are untouched.
+            assert_eq!(slot_color(&inverted, "green"), slot_color(&scheme, "green"));
+        }
+
+        #[test]
+        fn take_ansi_from() {
+            let mut scheme = ColorScheme::from_minttyrc(&read_fixture("tests/fixtures/Dracula.minttyrc")).unwrap();
+            let other = ColorScheme::from_iterm(&read_fixture("tests/fixtures/Dracula.itermcolors")).unwrap();
+            scheme.take_ansi_from(&other);
+            let yaml = scheme.to_yaml();
+            let other_yaml = other.to_yaml();
+            // UI colors stay, the ANSI palette is replaced.
+            assert!(yaml.contains("    background: '0x282a36'\n    foreground: '0xf8f8f2'\n"));
+            assert_eq!(yaml[yaml.find("  # Normal").unwrap()..], other_yaml[other_yaml.find("  # Normal").unwrap()..]);
+        }
+
+        #[test]
+        fn take_ui_from() {
+            let mut scheme = ColorScheme::from_minttyrc(&read_fixture("tests/fixtures/Dracula.minttyrc")).unwrap();
+            let original_yaml = scheme.to_yaml();
+            let other = ColorScheme::from_iterm(&read_fixture("tests/fixtures/DraculaBadge.itermcolors")).unwrap();
+            scheme.take_ui_from(&other);
+            let yaml = scheme.to_yaml();
+            assert!(yaml.contains("    background: '0x1e1f29'\n    foreground: '0xf8f8f2'\n"));
+            assert_eq!(yaml[yaml.find("  # Normal").unwrap()..], original_yaml[original_yaml.find("  # Normal").unwrap()..]);
+            assert!(scheme.to_iterm().contains("<key>Badge Color</key>"));
+        }
+
+        #[test]
+        fn from_terminal_app_all() {
+            let defaults = read_fixture("tests/fixtures/TerminalApp.plist");
+            let profiles = ColorScheme::from_terminal_app_all(&defaults).unwrap();
+            let names: Vec<&str> = profiles.iter().map(|(name, _)| name.as_ref()).collect();
+            assert_eq!(names, vec!["Basic", "Dracula"]);
+
+            let dracula_alacritty = ColorScheme::from_iterm(&read_fixture("tests/fixtures/Dracula.itermcolors")).unwrap().to_yaml();
+            assert_eq!(profiles[1].1.to_yaml(), dracula_alacritty);
+
+            // Basic uses grayscale NSWhite colors and leaves most slots unset.
+            let basic = &profiles[0].1;
+            assert_eq!(slot_color(basic, "background"), Color { red: 255, green: 255, blue: 255, alpha: None });
+            assert_eq!(slot_color(basic, "foreground"), Color { red: 0, green: 0, blue: 0, alpha: None });
+            assert_eq!(slot_color(basic, "red"), Color { red: 153, green: 0, blue: 0, alpha: None });
+        }
+
+        #[test]
+        fn from_bytes_defaults_export() {
+            let defaults = read_fixture("tests/fixtures/TerminalApp.plist");
+            let scheme = ColorScheme::from_bytes(defaults.as_bytes(), None).unwrap();
+            let dracula_iterm = read_fixture("tests/fixtures/Dracula.itermcolors");
+            assert_eq!(scheme.to_yaml(), ColorScheme::from_iterm(&dracula_iterm).unwrap().to_yaml());
+        }
+
+        #[test]
+        fn from_terminal_app_without_colors() {
+            let plist = "<plist version=\"1.0\"><dict><key>name</key><string>Empty</string></dict></plist>";
+            assert_eq!(ColorScheme::from_terminal_app(plist), Err(ColorError::InvalidFormat));
+            let missing_default = read_fixture("tests/fixtures/TerminalApp.plist")
+                .replace("<string>Dracula</string>", "<string>Missing</string>");
+            assert_eq!(ColorScheme::from_terminal_app(&missing_default), Err(ColorError::InvalidFormat));
+        }
+
+        #[test]
+        fn from_terminal_app_shared_refs() {
+            // Each array holds the next one twice; parsing it naively would
+            // build the last one 2^30 times.
+            let blob = "YnBsaXN0MDCiAQGiAgKiAwOiBASiBQWiBgaiBweiCAiiCQmiCgqiCwuiDAyiDQ2iDg6iDw+iEBCiERGiEhKiExOiFBSiFRWiFhaiFxeiGBiiGRmiGhqiGxuiHByiHR2iHh4ACAsOERQXGh0gIyYpLC8yNTg7PkFER0pNUFNWWVxfYgAAAAAAAAEBAAAAAAAAAB8AAAAAAAAAAAAAAAAAAABj";
+            let plist = format!("<plist version=\"1.0\"><dict><key>ANSIRedColor</key><data>{}</data></dict></plist>", blob);
+            assert_eq!(ColorScheme::from_terminal_app(&plist), Err(ColorError::InvalidFormat));
+        }
+
+        #[test]
+        fn from_terminal_app_all_without_window_settings() {
+            let dracula_iterm = read_fixture("tests/fixtures/Dracula.itermcolors");
+            assert_eq!(
+                ColorScheme::from_terminal_app_all(&dracula_iterm).err(),
+                Some(ColorError::InvalidFormat)
+            );
+        }
+
+        #[test]
+        fn quantize() {
+            let dracula_minttyrc = read_fixture("tests/fixtures/Dracula.minttyrc");
+            let scheme = ColorScheme::from_minttyrc(&dracula_minttyrc).unwrap();
+            assert_eq!(scheme.quantize(8).to_yaml(), scheme.to_yaml());
+
+            let yaml = scheme.quantize(1).to_yaml();
+            assert!(yaml.contains("    background: '0x000000'\n    foreground: '0xffffff'\n"));
+            assert!(yaml.contains("    red:     '0xff0000'\n"));
+        }
+
+        #[test]
+        fn to_minttyrc() {
+            let dracula_minttyrc = read_fixture("tests/fixtures/Dracula.minttyrc");
+            let scheme = ColorScheme::from_minttyrc(&dracula_minttyrc).unwrap();
+            assert_eq!(scheme.to_minttyrc(), dracula_minttyrc);
+        }
+
+        #[test]
+        fn patch_minttyrc_keeps_other_settings() {
+            let original = "Font=Consolas
+FontHeight=11
+# Colors
+ForegroundColour=1,2,3
+Red=4,5,6
+ThemeFile=dracula
+Locale=en_US
+";
+            let dracula_minttyrc = read_fixture("tests/fixtures/Dracula.minttyrc");
+            let mut scheme = ColorScheme::from_minttyrc(&dracula_minttyrc).unwrap();
+            let cursor = ColorScheme::from_minttyrc(
+                "CursorColour=191,191,191\nForegroundColour=248,248,242\nBackgroundColour=40,42,54\n"
+            ).unwrap();
+            scheme.take_ui_from(&cursor);
+            let patched = patch_minttyrc(original, &scheme);
+
+            assert!(patched.starts_with("Font=Consolas
+FontHeight=11
+# Colors
+ForegroundColour=248,248,242
+Red=255,85,85
+ThemeFile=dracula
+Locale=en_US
+"));
+            assert!(patched.contains("\nCursorColour=191,191,191\n"));
+            assert!(patched.ends_with("\nBoldWhite=230,230,230\n"));
+            assert_eq!(patched.matches("ForegroundColour=").count(), 1);
+        }
+
+        #[test]
+        fn patch_minttyrc_keeps_crlf() {
+            let dracula_minttyrc = read_fixture("tests/fixtures/Dracula.minttyrc");
+            let scheme = ColorScheme::from_minttyrc(&dracula_minttyrc).unwrap();
+            let patched = patch_minttyrc("Font=Consolas\r\nRed=4,5,6\r\n", &scheme);
+            assert!(patched.starts_with("Font=Consolas\r\nRed=255,85,85\r\n"));
+            assert!(!patched.replace("\r\n", "").contains('\n'));
+        }
+
+        #[test]
+        fn convert_vscode() {
+            let dracula_vscode = read_fixture("tests/fixtures/Dracula.vscode.json");
+            let dracula_iterm = read_fixture("tests/fixtures/Dracula.itermcolors");
+            assert_eq!(
+                ColorScheme::from_vscode(&dracula_vscode).unwrap().to_yaml(),
+                ColorScheme::from_iterm(&dracula_iterm).unwrap().to_yaml()
+            );
+        }
+
+        #[test]
+        fn from_vscode_customizations_only() {
+            let scheme = ColorScheme::from_vscode("{\"terminal.ansiRed\": \"#ff5555\", \"editor.background\": \"#000000\"}").unwrap();
+            assert_eq!(slot_color(&scheme, "red"), Color { red: 255, green: 85, blue: 85, alpha: None });
+        }
+
+        #[test]
+        fn from_vscode_invalid() {
+            assert_eq!(ColorScheme::from_vscode("[1, 2]").err(), Some(ColorError::InvalidFormat));
+            assert_eq!(ColorScheme::from_vscode("{\"terminal.ansiRed\": 1}").err(), Some(ColorError::InvalidFormat));
+            assert!(ColorScheme::from_vscode("{\"terminal.ansiRed\": \"#ff555\"}").is_err());
+
+            let no_terminal = "{\"workbench.colorCustomizations\": {\"editor.background\": \"#000000\"}, \"editor.fontSize\": 14}";
+            assert_eq!(ColorScheme::from_vscode(no_terminal).err(), Some(ColorError::InvalidFormat));
+            assert_eq!(ColorScheme::from_vscode("{}").err(), Some(ColorError::InvalidFormat));
+        }
+
+        #[test]
+        fn to_json_without_meta() {
+            let dracula_minttyrc = read_fixture("tests/fixtures/Dracula.minttyrc");
+            let exported = ColorScheme::from_minttyrc(&dracula_minttyrc).unwrap().to_json();
+            let parsed = json::parse(&exported).unwrap();
+            assert!(!parsed.has_key("meta"));
+            assert_eq!(parsed["background"], "#282a36");
+            assert_eq!(parsed["normal"]["red"], "#ff5555");
+            assert_eq!(parsed["bright"]["white"], "#e6e6e6");
+        }
+
+        #[test]
+        fn to_json_with_meta() {
+            let dracula_minttyrc = read_fixture("tests/fixtures/Dracula.minttyrc");
+            let exported = ColorScheme::from_minttyrc(&dracula_minttyrc).unwrap()
+                .to_json_with(JsonOptions { with_meta: true });
+            assert!(exported.starts_with("{\"meta\":{"));
+
+            let parsed = json::parse(&exported).unwrap();
+            let meta = &parsed["meta"];
+            assert_eq!(meta["is_dark"], true);
+            let avg_luminance = meta["avg_luminance"].as_f64().unwrap();
+            assert!(avg_luminance > 0.0 && avg_luminance < 1.0);
+            let min_contrast = meta["min_contrast"].as_f64().unwrap();
+            assert!(min_contrast > 3.0 && min_contrast < 21.0);
+            assert_eq!(parsed["foreground"], "#f8f8f2");
+        }
+
+        #[test]
+        fn merge_partial_minttyrc() {
+            let dracula_minttyrc = read_fixture("tests/fixtures/Dracula.minttyrc");
+            let mut scheme = ColorScheme::from_minttyrc(&dracula_minttyrc).unwrap();
+            let overrides = ColorScheme::from_minttyrc("Red=1,2,3\nBackgroundColour=0,0,0\n").unwrap();
+            scheme.merge(&overrides);
+
+            assert_eq!(slot_color(&scheme, "red"), Color { red: 1, green: 2, blue: 3, alpha: None });
+            assert_eq!(slot_color(&scheme, "background"), Color { red: 0, green: 0, blue: 0, alpha: None });
+            assert_eq!(slot_color(&scheme, "foreground"), Color { red: 248, green: 248, blue: 242, alpha: None });
+            assert_eq!(slot_color(&scheme, "green"), Color { red: 80, green: 250, blue: 123, alpha: None });
+        }
+
+        #[test]
+        fn merge_empty_is_noop() {
+            let dracula_minttyrc = read_fixture("tests/fixtures/Dracula.minttyrc");
+            let mut scheme = ColorScheme::from_minttyrc(&dracula_minttyrc).unwrap();
+            scheme.merge(&ColorScheme::default());
+            assert_eq!(scheme.to_minttyrc(), ColorScheme::from_minttyrc(&dracula_minttyrc).unwrap().to_minttyrc());
+        }
+
+        #[test]
+        fn to_minttyrc_skips_unset() {
+            let scheme = ColorScheme::from_minttyrc("Red=1,2,3\n").unwrap();
+            assert_eq!(scheme.to_minttyrc(), "Red=1,2,3\n");
+        }
+
+        #[test]
+        fn from_json_round_trip() {
+            let dracula_minttyrc = read_fixture("tests/fixtures/Dracula.minttyrc");
+            let exported = ColorScheme::from_minttyrc(&dracula_minttyrc).unwrap().to_json();
+            assert_eq!(ColorScheme::from_json(&exported).unwrap().to_json(), exported);
+        }
+
+        #[test]
+        fn from_json_palette_16() {
+            let content = "{\"foreground\": \"#f8f8f2\", \"background\": \"#282a36\", \"palette\": [
+                \"#000000\", \"#ff5555\", \"#50fa7b\", \"#f1fa8c\", \"#bd93f9\", \"#ff79c6\", \"#8be9fd\", \"#bfbfbf\",
+                \"#4d4d4d\", \"#ff6e67\", \"#5af78e\", \"#f4f99d\", \"#caa9fa\", \"#ff92d0\", \"#9aedfe\", \"#e6e6e6\"]}";
+            let parsed = json::parse(&ColorScheme::from_json(content).unwrap().to_json()).unwrap();
+            assert_eq!(parsed["normal"]["red"], "#ff5555");
+            assert_eq!(parsed["bright"]["black"], "#4d4d4d");
+            assert_eq!(parsed["bright"]["white"], "#e6e6e6");
+        }
+
+        #[test]
+        fn from_json_palette_8() {
+            let content = "{\"foreground\": \"#f8f8f2\", \"background\": \"#282a36\", \"palette\": [
+                \"#000000\", \"#ff0000\", \"#50fa7b\", \"#f1fa8c\", \"#bd93f9\", \"#ff79c6\", \"#8be9fd\", \"#bfbfbf\"]}";
+            let scheme = ColorScheme::from_json(content).unwrap();
+            let parsed = json::parse(&scheme.to_json()).unwrap();
+            assert_eq!(parsed["normal"]["red"], "#ff0000");
+            assert_eq!(parsed["bright"]["red"], "#ff6666");
+            assert_eq!(parsed["bright"]["black"], "#333333");
+            assert_eq!(scheme.to_iterm().matches("<key>Ansi ").count(), 16);
+        }
+
+        #[test]
+        fn from_json_invalid() {
+            assert_eq!(ColorScheme::from_json("{\"palette\": [\"#000000\"]}").err(), Some(ColorError::InvalidFormat));
+            assert_eq!(ColorScheme::from_json("{\"palette\": \"#000000\"}").err(), Some(ColorError::InvalidFormat));
+            assert_eq!(ColorScheme::from_json("[]").err(), Some(ColorError::InvalidFormat));
+        }
+
+        #[test]
+        fn iter_order() {
+            let dracula_minttyrc = read_fixture("tests/fixtures/Dracula.minttyrc");
+            let scheme = ColorScheme::from_minttyrc(&dracula_minttyrc).unwrap();
+            let names: Vec<&str> = scheme.iter().map(|(name, _)| name).collect();
+            assert_eq!(names, vec![
+                "foreground", "background",
+                "black", "red", "green", "yellow", "blue", "magenta", "cyan", "white",
+                "bright_black", "bright_red", "bright_green", "bright_yellow",
+                "bright_blue", "bright_magenta", "bright_cyan", "bright_white",
+            ]);
+            assert_eq!(scheme.iter().nth(3), Some(("red", &Color { red: 255, green: 85, blue: 85, alpha: None })));
+        }
+
+        #[test]
+        fn iter_unset_is_black() {
+            let scheme = ColorScheme::default();
+            assert_eq!(scheme.iter().count(), 18);
+            assert!(scheme.iter().all(|(_, color)| *color == Color::default()));
+        }
+
+        #[test]
+        fn to_svg_swatch_ids() {
+            let dracula_minttyrc = read_fixture("tests/fixtures/Dracula.minttyrc");
+            let scheme = ColorScheme::from_minttyrc(&dracula_minttyrc).unwrap();
+            for &labels in &[LabelMode::Text, LabelMode::Tooltip, LabelMode::None] {
+                let svg = scheme.to_svg_with(SvgOptions { labels });
+                for (name, _) in scheme.iter().skip(2) {
+                    assert_eq!(svg.matches(&format!("<rect id=\"{}\" ", name)).count(), 1, "{}", name);
+                }
+            }
+            assert!(scheme.to_svg().contains("<rect id=\"bright_blue\" x=\"384\""));
+        }
+
+        #[test]
+        fn from_iterm_rounds_components() {
+            let rounding_iterm = read_fixture("tests/fixtures/Rounding.itermcolors");
+            let scheme = ColorScheme::from_iterm(&rounding_iterm).unwrap();
+            assert_eq!(slot_color(&scheme, "background"), Color { red: 128, green: 128, blue: 128, alpha: None });
+            assert_eq!(slot_color(&scheme, "red"), Color { red: 255, green: 1, blue: 128, alpha: None });
+        }
+
+        #[test]
+        fn from_iterm_alpha_component() {
+            let alpha_iterm = read_fixture("tests/fixtures/Alpha.itermcolors");
+            let scheme = ColorScheme::from_iterm(&alpha_iterm).unwrap();
+            assert_eq!(slot_color(&scheme, "background"), Color { red: 40, green: 42, blue: 54, alpha: Some(204) });
+            assert_eq!(slot_color(&scheme, "foreground"), Color { red: 248, green: 248, blue: 242, alpha: None });
+
+            let exported = scheme.to_iterm();
+            assert!(exported.contains("\t<key>Background Color</key>
+\t<dict>
+\t\t<key>Alpha Component</key>
+\t\t<real>0.8</real>
+\t\t<key>Blue Component</key>"));
+            assert_eq!(exported.matches("Alpha Component").count(), 1);
+            assert_eq!(ColorScheme::from_iterm(&exported).unwrap(), scheme);
+        }
+
+        #[test]
+        fn from_foot() {
+            let dracula_foot = read_fixture("tests/fixtures/Dracula.foot.ini");
+            let dracula_minttyrc = read_fixture("tests/fixtures/Dracula.minttyrc");
+            assert_eq!(
+                ColorScheme::from_foot(&dracula_foot).unwrap().to_yaml(),
+                ColorScheme::from_minttyrc(&dracula_minttyrc).unwrap().to_yaml()
+            );
+        }
+
+        #[test]
+        fn to_foot_round_trip() {
+            let dracula_minttyrc = read_fixture("tests/fixtures/Dracula.minttyrc");
+            let exported = ColorScheme::from_minttyrc(&dracula_minttyrc).unwrap().to_foot();
+            assert!(exported.starts_with("[colors]\nforeground=f8f8f2\nbackground=282a36\nregular0=000000\n"));
+            assert!(exported.ends_with("bright7=e6e6e6\n"));
+            assert_eq!(ColorScheme::from_foot(&exported).unwrap().to_foot(), exported);
+        }
+
+        #[test]
+        fn from_foot_missing_keys() {
+            let dracula_foot = read_fixture("tests/fixtures/Dracula.foot.ini");
+            let without_regular5 = dracula_foot.replace("regular5=ff79c6\n", "");
+            assert_eq!(
+                ColorScheme::from_foot(&without_regular5).err(),
+                Some(ColorError::MissingKeys(vec!["regular5".to_string()]))
+            );
+
+            let only_regular = "[colors]\nregular0=000000\nregular1=ff5555\nregular2=50fa7b\nregular3=f1fa8c\n\
+                                regular4=caa9fa\nregular5=ff79c6\nregular6=8be9fd\nregular7=bfbfbf\n";
+            match ColorScheme::from_foot(only_regular) {
+                Err(ColorError::MissingKeys(keys)) => assert_eq!(keys.len(), 8),
+                _ => panic!("expected missing bright keys"),
+            }
+        }
+
+        #[test]
+        fn from_foot_invalid() {
+            assert_eq!(ColorScheme::from_foot("[colors]\nregular0\n").err(), Some(ColorError::InvalidFormat));
+            assert_eq!(ColorScheme::from_foot("[colors]\nregular0=#000000\n").err(), Some(ColorError::InvalidFormat));
+        }
+
+        #[test]
+        fn to_iterm2_dynamic() {
+            let dracula_minttyrc = read_fixture("tests/fixtures/Dracula.minttyrc");
+            let scheme = ColorScheme::from_minttyrc(&dracula_minttyrc).unwrap();
+            let parsed = json::parse(&scheme.to_iterm2_dynamic()).unwrap();
+            assert_eq!(parsed["Profiles"].len(), 1);
+
+            let profile = &parsed["Profiles"][0];
+            assert_eq!(profile["Name"], "colortty");
+            assert_eq!(profile["Guid"].as_str().unwrap().len(), 36);
+            for i in 0..16 {
+                assert!(profile.has_key(&format!("Ansi {} Color", i)), "Ansi {} Color", i);
+            }
+            let channel = |key: &str, component: &str| (profile[key][component].as_f64().unwrap() * 255.0).round();
+            assert_eq!(channel("Ansi 1 Color", "Red Component"), 255.0);
+            assert_eq!(channel("Ansi 1 Color", "Green Component"), 85.0);
+            assert_eq!(channel("Background Color", "Blue Component"), 54.0);
+            assert_eq!(channel("Foreground Color", "Red Component"), 248.0);
+            assert!(!profile.has_key("Badge Color"));
+        }
+
+        #[test]
+        fn to_iterm2_dynamic_guid() {
+            let dracula_minttyrc = read_fixture("tests/fixtures/Dracula.minttyrc");
+            let scheme = ColorScheme::from_minttyrc(&dracula_minttyrc).unwrap();
+            let guid_of = |exported: String| json::parse(&exported).unwrap()["Profiles"][0]["Guid"].to_string();
+            let guid = guid_of(scheme.to_iterm2_dynamic());
+            assert_eq!(guid_of(scheme.to_iterm2_dynamic()), guid);
+            assert_ne!(guid_of(scheme.to_iterm2_dynamic_named("Dracula")), guid);
+            assert_eq!(guid_of(scheme.swap_fg_bg().to_iterm2_dynamic()), guid);
+            assert_eq!(guid_of(scheme.desaturate().to_iterm2_dynamic_named("Dracula")), guid_of(scheme.to_iterm2_dynamic_named("Dracula")));
+        }
+
+        #[test]
+        fn from_bytes_detects_format() {
+            let expected = ColorScheme::from_iterm(&read_fixture("tests/fixtures/Dracula.itermcolors")).unwrap().to_yaml();
+            for fixture in &["Dracula.itermcolors", "Dracula.vscode.json"] {
+                let data = read_fixture(&format!("tests/fixtures/{}", fixture));
+                assert_eq!(ColorScheme::from_bytes(data.as_bytes(), None).unwrap().to_yaml(), expected, "{}", fixture);
+            }
+
+            let expected = ColorScheme::from_minttyrc(&read_fixture("tests/fixtures/Dracula.minttyrc")).unwrap().to_yaml();
+            for fixture in &["Dracula.minttyrc", "Dracula.foot.ini"] {
+                let data = read_fixture(&format!("tests/fixtures/{}", fixture));
+                assert_eq!(ColorScheme::from_bytes(data.as_bytes(), None).unwrap().to_yaml(), expected, "{}", fixture);
+            }
+        }
+
+        #[test]
+        fn from_bytes_with_hint() {
+            let dracula_foot = read_fixture("tests/fixtures/Dracula.foot.ini");
+            let scheme = ColorScheme::from_bytes(dracula_foot.as_bytes(), Some(ColorSchemeFormat::Foot)).unwrap();
+            assert_eq!(scheme.to_foot(), ColorScheme::from_foot(&dracula_foot).unwrap().to_foot());
+            assert_eq!(
+                ColorScheme::from_bytes(dracula_foot.as_bytes(), Some(ColorSchemeFormat::Json)).err(),
+                Some(ColorError::InvalidFormat)
+            );
+        }
+
+        #[test]
+        fn from_bytes_errors() {
+            assert_eq!(ColorScheme::from_bytes(b"hello", None).err(), Some(ColorError::UnknownFormat));
+            match ColorScheme::from_bytes(b"Red=\xff,0,0", None) {
+                Err(ColorError::Utf8(_)) => (),
+                _ => panic!("expected a UTF-8 error"),
+            }
+        }
+
+        #[test]
+        fn to_ghostty() {
+            let dracula_minttyrc = read_fixture("tests/fixtures/Dracula.minttyrc");
+            let config = ColorScheme::from_minttyrc(&dracula_minttyrc).unwrap().to_ghostty();
+            assert!(config.starts_with("foreground = #f8f8f2\nbackground = #282a36\npalette = 0=#000000\npalette = 1=#ff5555\n"));
+            assert!(config.ends_with("palette = 15=#e6e6e6\n"));
+            assert_eq!(config.lines().count(), 18);
+        }
+
+        #[test]
+        fn fill_from_defaults() {
+            let mut scheme = ColorScheme::from_minttyrc("ForegroundColour=248,248,242\nRed=255,85,85\n").unwrap();
+            scheme.fill_from_defaults();
+            let colors: Vec<(&str, Color)> = scheme.iter().map(|(name, color)| (name, *color)).collect();
+            assert_eq!(colors[0], ("foreground", Color { red: 248, green: 248, blue: 242, alpha: None }));
+            assert_eq!(colors[1], ("background", Color { red: 0, green: 0, blue: 0, alpha: None }));
+            assert_eq!(colors[3], ("red", Color { red: 255, green: 85, blue: 85, alpha: None }));
+            for &(name, color) in &colors[2..] {
+                if name != "red" {
+                    assert_eq!(Some(color), Color::from_ansi_name(name), "{}", name);
+                }
+            }
+            assert_eq!(scheme.to_minttyrc().lines().count(), 18);
+        }
+
+        #[test]
+        fn fill_from_defaults_foreground() {
+            let mut scheme = ColorScheme::default();
+            scheme.fill_from_defaults();
+            assert_eq!(slot_color(&scheme, "foreground"), Color { red: 255, green: 255, blue: 255, alpha: None });
+        }
+
+        #[test]
+        fn from_minttyrc_error_location() {
+            let content = "# Dracula\nForegroundColour=248,248,242\n\nRed=2fo,3,4\n";
+            let error = ColorScheme::from_minttyrc(content).err().unwrap();
+            assert_eq!(error.to_string(), "invalid color on line 4: 'Red=2fo,3,4': invalid number: invalid digit found in string");
+            match error {
+                ColorError::InvalidLine { line: 4, cause, .. } => match *cause {
+                    ColorError::ParseInt(_) => (),
+                    other => panic!("unexpected cause {:?}", other),
+                },
+                other => panic!("unexpected error {:?}", other),
+            }
+        }
+
+        #[test]
+        fn from_iterm_error_key() {
+            let dracula_iterm = read_fixture("tests/fixtures/Dracula.itermcolors");
+            let broken = dracula_iterm.replacen("<real>0.3333333432674408</real>", "<real>one third</real>", 1);
+            let error = ColorScheme::from_iterm(&broken).err().unwrap();
+            assert_eq!(error.to_string(), "invalid color for key 'Ansi 1 Color': invalid format");
+            assert_eq!(
+                error,
+                ColorError::InvalidKey { key: "Ansi 1 Color".to_string(), cause: Box::new(ColorError::InvalidFormat) }
+            );
+            assert_eq!(ColorScheme::from_iterm("not xml").err(), Some(ColorError::InvalidFormat));
+
+            let empty = dracula_iterm.replacen("<real>0.3333333432674408</real>", "<real></real>", 1);
+            assert_eq!(
+                ColorScheme::from_iterm(&empty).err(),
+                Some(ColorError::InvalidKey { key: "Ansi 1 Color".to_string(), cause: Box::new(ColorError::InvalidFormat) })
+            );
+        }
+
+        #[test]
+        fn to_json_canonical() {
+            let dracula_minttyrc = read_fixture("tests/fixtures/Dracula.minttyrc");
+            let exported = ColorScheme::from_minttyrc(&dracula_minttyrc).unwrap().to_json();
+            assert_eq!(exported, concat!(
+                "{\"foreground\":\"#f8f8f2\",\"background\":\"#282a36\",",
+                "\"normal\":{\"black\":\"#000000\",\"red\":\"#ff5555\",\"green\":\"#50fa7b\",\"yellow\":\"#f1fa8c\",",
+                "\"blue\":\"#caa9fa\",\"magenta\":\"#ff79c6\",\"cyan\":\"#8be9fd\",\"white\":\"#bfbfbf\"},",
+                "\"bright\":{\"black\":\"#282a35\",\"red\":\"#ff6e67\",\"green\":\"#5af78e\",\"yellow\":\"#f4f99d\",",
+                "\"blue\":\"#caa9fa\",\"magenta\":\"#ff92d0\",\"cyan\":\"#9aedfe\",\"white\":\"#e6e6e6\"}}",
+            ));
+        }
+
+        #[test]
+        fn to_json_alpha() {
+            let alpha_iterm = read_fixture("tests/fixtures/Alpha.itermcolors");
+            let scheme = ColorScheme::from_iterm(&alpha_iterm).unwrap();
+            let exported = scheme.to_json();
+            assert!(exported.contains("\"background\":\"#282a36cc\""));
+            assert!(exported.contains("\"foreground\":\"#f8f8f2\""));
+            assert_eq!(ColorScheme::from_json(&exported).unwrap().to_json(), exported);
+        }
+
+        #[test]
+        fn from_iterm_missing_component() {
+            let missing_iterm = read_fixture("tests/fixtures/MissingComponent.itermcolors");
+            let error = ColorScheme::from_iterm(&missing_iterm).err().unwrap();
+            assert_eq!(error.to_string(), "invalid color for key 'Ansi 2 Color': missing keys: Green Component");
+            assert_eq!(error, ColorError::InvalidKey {
+                key: "Ansi 2 Color".to_string(),
+                cause: Box::new(ColorError::MissingKeys(vec!["Green Component".to_string()])),
+            });
+        }
+
+        #[test]
+        fn to_yaml_without_dim() {
+            let dracula_minttyrc = read_fixture("tests/fixtures/Dracula.minttyrc");
+            assert!(!ColorScheme::from_minttyrc(&dracula_minttyrc).unwrap().to_yaml().contains("dim"));
+        }
+
+        #[test]
+        fn fill_dim_from_normal() {
+            let mut scheme = ColorScheme::from_minttyrc("Black=0,0,0\nRed=255,102,102\nWhite=191,191,191\n").unwrap();
+            scheme.fill_dim_from_normal();
+            let yaml = scheme.to_yaml();
+            assert!(yaml.ends_with("
+  # Dim colors
+  dim:
+    black:   '0x000000'
+    red:     '0xff0000'
+    white:   '0x8c8c8c'
+"));
+        }
+
+        #[test]
+        fn to_toml_dim() {
+            let mut scheme = ColorScheme::from_minttyrc("Black=0,0,0\nRed=255,102,102\n").unwrap();
+            assert!(!scheme.to_toml().contains("dim"));
+            scheme.fill_dim_from_normal();
+            assert!(scheme.to_toml().ends_with("
+[colors.dim]
+black = \"0x000000\"
+red = \"0xff0000\"
+"));
+        }
+
+        #[test]
+        fn eq_and_diff() {
+            let dracula_minttyrc = read_fixture("tests/fixtures/Dracula.minttyrc");
+            let scheme = ColorScheme::from_minttyrc(&dracula_minttyrc).unwrap();
+            let round_tripped = ColorScheme::from_minttyrc(&scheme.to_minttyrc()).unwrap();
+            assert_eq!(round_tripped, scheme);
+            assert!(scheme.diff(&round_tripped).is_empty());
+
+            let swapped = scheme.swap_fg_bg();
+            assert!(swapped != scheme);
+            let foreground = Color { red: 248, green: 248, blue: 242, alpha: None };
+            let background = Color { red: 40, green: 42, blue: 54, alpha: None };
+            assert_eq!(scheme.diff(&swapped), vec![
+                ("foreground", foreground, background),
+                ("background", background, foreground),
+            ]);
+        }
+
+        #[test]
+        #[cfg(feature = "gzip")]
+        fn from_bytes_gzip() {
+            let mut compressed = Vec::new();
+            File::open("tests/fixtures/Dracula.itermcolors.gz")
+                .unwrap()
+                .read_to_end(&mut compressed)
+                .unwrap();
+            let dracula_iterm = read_fixture("tests/fixtures/Dracula.itermcolors");
+            assert_eq!(
+                ColorScheme::from_bytes(&compressed, None).unwrap(),
+                ColorScheme::from_iterm(&dracula_iterm).unwrap()
+            );
+            assert_eq!(ColorScheme::from_bytes(&compressed[..20], None).err(), Some(ColorError::InvalidFormat));
+        }
+
+        #[test]
+        fn from_minttyrc_lenient() {
+            let dracula_minttyrc = read_fixture("tests/fixtures/Dracula.minttyrc");
+            let with_settings = format!("Font=Consolas\nFontHeight=11\n{}Term=xterm-256color\n", dracula_minttyrc);
+            assert_eq!(
+                ColorScheme::from_minttyrc(&with_settings).unwrap(),
+                ColorScheme::from_minttyrc(&dracula_minttyrc).unwrap()
+            );
+            assert!(ColorScheme::from_minttyrc("Red=1,2\n").is_err());
+        }
+
+        #[test]
+        fn from_minttyrc_strict() {
+            let strict = ParseOptions { strict: true };
+            let dracula_minttyrc = read_fixture("tests/fixtures/Dracula.minttyrc");
+            assert!(ColorScheme::from_minttyrc_with(&dracula_minttyrc, strict).is_ok());
+
+            match ColorScheme::from_minttyrc_with(&format!("Font=Consolas\n{}", dracula_minttyrc), strict) {
+                Err(ColorError::InvalidLine { line: 1, .. }) => (),
+                other => panic!("unexpected result {:?}", other.err()),
+            }
+
+            match ColorScheme::from_minttyrc_with("Red=1,2,3\r\nPurple=1,2,3\r\n", strict) {
+                Err(ColorError::InvalidLine { line: 2, text, .. }) => assert_eq!(text, "Purple=1,2,3"),
+                other => panic!("unexpected result {:?}", other.err()),
+            }
+
+            let without_blue = dracula_minttyrc.replace("BoldBlue=202,169,250\n", "");
+            assert_eq!(
+                ColorScheme::from_minttyrc_with(&without_blue, strict).err(),
+                Some(ColorError::MissingKeys(vec!["BoldBlue".to_string()]))
+            );
+            assert!(ColorScheme::from_minttyrc(&without_blue).is_ok());
+        }
+
+        #[test]
+        fn from_iterm_strict() {
+            let strict = ParseOptions { strict: true };
+            let dracula_iterm = read_fixture("tests/fixtures/Dracula.itermcolors");
+            assert_eq!(
+                ColorScheme::from_iterm_with(&dracula_iterm, strict).unwrap(),
+                ColorScheme::from_iterm(&dracula_iterm).unwrap()
+            );
+
+            let unknown = dracula_iterm.replacen("<key>Ansi 0 Color</key>", "<key>Ansi 16 Color</key>", 1);
+            assert!(ColorScheme::from_iterm(&unknown).is_ok());
+            assert_eq!(
+                ColorScheme::from_iterm_with(&unknown, strict).err(),
+                Some(ColorError::InvalidKey { key: "Ansi 16 Color".to_string(), cause: Box::new(ColorError::InvalidFormat) })
+            );
+
+            let rounding_iterm = read_fixture("tests/fixtures/Rounding.itermcolors");
+            match ColorScheme::from_iterm_with(&rounding_iterm, strict) {
+                Err(ColorError::MissingKeys(keys)) => {
+                    assert_eq!(keys.len(), 16);
+                    assert_eq!(keys[..2], ["Ansi 0 Color".to_string(), "Ansi 2 Color".to_string()]);
+                    assert!(keys.contains(&"Foreground Color".to_string()));
+                }
+                other => panic!("unexpected result {:?}", other.err()),
+            }
+        }
+
+        #[test]
+        fn infer_fg_bg() {
+            let dracula_minttyrc = read_fixture("tests/fixtures/Dracula.minttyrc");
+            let ansi_only: String = dracula_minttyrc.lines()
+                .filter(|line| !line.contains("groundColour"))
+                .map(|line| format!("{}\n", line))
+                .collect();
+            let mut scheme = ColorScheme::from_minttyrc(&ansi_only).unwrap();
+            scheme.infer_fg_bg();
+            assert!(scheme.to_yaml().starts_with("colors:
+  # Default colors
+  primary:
+    background: '0x000000'
+    foreground: '0xbfbfbf'
+"));
+
+            let mut full = ColorScheme::from_minttyrc(&dracula_minttyrc).unwrap();
+            full.infer_fg_bg();
+            assert_eq!(full, ColorScheme::from_minttyrc(&dracula_minttyrc).unwrap());
+        }
+
+        #[test]
+        fn to_256_palette() {
+            let dracula_minttyrc = read_fixture("tests/fixtures/Dracula.minttyrc");
+            let palette = ColorScheme::from_minttyrc(&dracula_minttyrc).unwrap().to_256_palette();
+            assert_eq!(palette.len(), 256);
+            assert_eq!(palette[1], Color { red: 255, green: 85, blue: 85, alpha: None });
+            assert_eq!(palette[16], Color { red: 0, green: 0, blue: 0, alpha: None });
+            assert_eq!(palette[17], Color { red: 0, green: 0, blue: 95, alpha: None });
+            assert_eq!(palette[196], Color { red: 255, green: 0, blue: 0, alpha: None });
+            assert_eq!(palette[231], Color { red: 255, green: 255, blue: 255, alpha: None });
+            assert_eq!(palette[232], Color { red: 8, green: 8, blue: 8, alpha: None });
+            assert_eq!(palette[255], Color { red: 238, green: 238, blue: 238, alpha: None });
+        }
+
+        #[test]
+        fn to_yaml_extended() {
+            let dracula_minttyrc = read_fixture("tests/fixtures/Dracula.minttyrc");
+            let mut scheme = ColorScheme::from_minttyrc(&dracula_minttyrc).unwrap();
+            assert_eq!(scheme.to_yaml_extended(), scheme.to_yaml());
+
+            scheme.set_indexed_color(17, Color { red: 1, green: 2, blue: 3, alpha: None }).unwrap();
+            let before = scheme.clone();
+            assert_eq!(scheme.set_indexed_color(15, Color::default()), Err(ColorError::InvalidFormat));
+            assert_eq!(scheme, before);
+            scheme.fill_256_palette();
+            assert_eq!(scheme.to_256_palette()[17], Color { red: 1, green: 2, blue: 3, alpha: None });
+
+            let yaml = scheme.to_yaml_extended();
+            assert!(yaml.starts_with(&scheme.to_yaml()));
+            assert!(yaml.contains("
+  indexed_colors:
+    - { index: 16, color: '0x000000' }
+    - { index: 17, color: '0x010203' }
+    - { index: 18, color: '0x000087' }
+"));
+            assert!(yaml.ends_with("    - { index: 255, color: '0xeeeeee' }\n"));
+            assert_eq!(yaml.matches("- { index: ").count(), 240);
+        }
+
+        #[test]
+        fn from_minttyrc_duplicate_keys() {
+            let dracula_minttyrc = read_fixture("tests/fixtures/Dracula.minttyrc");
+            let (_, warnings) = ColorScheme::from_minttyrc_with_warnings(&dracula_minttyrc, ParseOptions::default()).unwrap();
+            assert!(warnings.is_empty());
+
+            let duplicated = format!("{}Red=1,2,3\nFont=Consolas\nFont=Consolas\n", dracula_minttyrc);
+            let (scheme, warnings) = ColorScheme::from_minttyrc_with_warnings(&duplicated, ParseOptions::default()).unwrap();
+            assert_eq!(warnings, vec![ValidationWarning::DuplicateKey("Red".to_string())]);
+            assert_eq!(slot_color(&scheme, "red"), Color { red: 1, green: 2, blue: 3, alpha: None });
+        }
+
+        #[test]
+        fn from_iterm_duplicate_keys() {
+            let dracula_iterm = read_fixture("tests/fixtures/Dracula.itermcolors");
+            let (_, warnings) = ColorScheme::from_iterm_with_warnings(&dracula_iterm, ParseOptions::default()).unwrap();
+            assert!(warnings.is_empty());
+
+            let duplicated = dracula_iterm.replacen("<key>Ansi 0 Color</key>", "<key>Ansi 1 Color</key>", 1);
+            let (_, warnings) = ColorScheme::from_iterm_with_warnings(&duplicated, ParseOptions::default()).unwrap();
+            assert_eq!(warnings, vec![ValidationWarning::DuplicateKey("Ansi 1 Color".to_string())]);
+        }
+
+        #[test]
+        fn desaturate() {
+            let dracula_minttyrc = read_fixture("tests/fixtures/Dracula.minttyrc");
+            let scheme = ColorScheme::from_minttyrc(&dracula_minttyrc).unwrap();
+            let gray = scheme.desaturate();
+            for ((name, original), (_, color)) in scheme.iter().zip(gray.iter()) {
+                assert_eq!(*color, original.grayscale(), "{}", name);
+                assert!(color.red == color.green && color.green == color.blue, "{}", name);
+            }
+        }
+
+        struct FailingIo;
+
+        impl Read for FailingIo {
+            fn read(&mut self, _: &mut [u8]) -> io::Result<usize> {
+                Err(io::Error::new(io::ErrorKind::BrokenPipe, "closed"))
+            }
+        }
+
+        impl Write for FailingIo {
+            fn write(&mut self, _: &[u8]) -> io::Result<usize> {
+                Err(io::Error::new(io::ErrorKind::BrokenPipe, "closed"))
+            }
+
+            fn flush(&mut self) -> io::Result<()> {
+                Ok(())
+            }
+        }
+
+        #[test]
+        fn convert_reader_writes_yaml() {
+            let input = read_fixture("tests/fixtures/Dracula.minttyrc");
+            let mut output = Vec::new();
+            convert_reader(Cursor::new(input.as_bytes()), &mut output, ColorSchemeFormat::Mintty).unwrap();
+            let expected = ColorScheme::from_minttyrc(&input).unwrap().to_yaml();
+            assert_eq!(expected, String::from_utf8(output).unwrap());
+        }
+
+        #[test]
+        fn convert_reader_io_errors() {
+            let input = read_fixture("tests/fixtures/Dracula.minttyrc");
+            let broken_pipe = ColorError::Io(io::Error::new(io::ErrorKind::BrokenPipe, ""));
+            assert_eq!(
+                Err(broken_pipe),
+                convert_reader(Cursor::new(input.as_bytes()), FailingIo, ColorSchemeFormat::Mintty)
+            );
+            let err = convert_reader(FailingIo, Vec::new(), ColorSchemeFormat::Mintty).unwrap_err();
+            assert_eq!("I/O error: closed", err.to_string());
+        }
+
+        #[test]
+        fn from_iterm_bold_color_without_brights() {
+            let bold_iterm = read_fixture("tests/fixtures/BoldOnly.itermcolors");
+            let imported = ColorScheme::from_iterm(&bold_iterm).unwrap();
+            let exported = imported.to_iterm();
+            assert_eq!(exported.matches("<key>Ansi ").count(), 8);
+            assert!(exported.contains("\t<key>Bold Color</key>"));
+            assert!(exported.contains("\t<key>Cursor Text Color</key>"));
+
+            let mut scheme = imported.clone();
+            scheme.fill_brights_from_bold();
+            let colors: Vec<(&str, &Color)> = scheme.iter().collect();
+            assert_eq!(("bright_white", &Color::from_hex("#ffffff").unwrap()), colors[17]);
+            let red = Color::from_hex("#ff5555").unwrap();
+            assert_eq!(("bright_red", &red.lighten(0.2)), colors[11]);
+
+            // Merging the import keeps the base's brights.
+            let dracula_iterm = read_fixture("tests/fixtures/Dracula.itermcolors");
+            let mut base = ColorScheme::from_iterm(&dracula_iterm).unwrap();
+            let bright_red = *base.iter().nth(11).unwrap().1;
+            base.merge(&imported);
+            assert_eq!(base.iter().nth(11).unwrap().1, &bright_red);
+        }
+
+        #[test]
+        fn from_iterm_bold_color_strict() {
+            let bold_iterm = read_fixture("tests/fixtures/BoldOnly.itermcolors");
+            match ColorScheme::from_iterm_with(&bold_iterm, ParseOptions { strict: true }) {
+                Err(ColorError::MissingKeys(keys)) => {
+                    let expected: Vec<String> = (8..16).map(|n| format!("Ansi {} Color", n)).collect();
+                    assert_eq!(keys, expected);
+                }
+                other => panic!("unexpected result {:?}", other.err()),
+            }
+        }
+
+        #[test]
+        fn from_iterm_bold_color_keeps_brights() {
+            let dracula_iterm = read_fixture("tests/fixtures/Dracula.itermcolors");
+            let scheme = ColorScheme::from_iterm(&dracula_iterm).unwrap();
+            let bright_red = scheme.iter().nth(11).unwrap();
+            assert_eq!(("bright_red", &Color::from_hex("#ff5555").unwrap()), bright_red);
+        }
+
+        #[test]
+        fn is_dark_theme() {
+            let dracula_minttyrc = read_fixture("tests/fixtures/Dracula.minttyrc");
+            let scheme = ColorScheme::from_minttyrc(&dracula_minttyrc).unwrap();
+            assert!(scheme.is_dark_theme());
+            assert!(!scheme.swap_fg_bg().is_dark_theme());
+            assert!(ColorScheme::default().is_dark_theme());
+        }
+
+        #[test]
+        fn all_from_iterm() {
+            let bundle = read_fixture("tests/fixtures/Bundle.itermcolors");
+            let schemes = ColorScheme::all_from_iterm(&bundle).unwrap();
+            let names: Vec<&str> = schemes.iter().map(|(name, _)| name.as_str()).collect();
+            assert_eq!(names, ["Dracula", "Dracula Badge"]);
+            let dracula = ColorScheme::from_iterm(&read_fixture("tests/fixtures/Dracula.itermcolors")).unwrap();
+            let badge = ColorScheme::from_iterm(&read_fixture("tests/fixtures/DraculaBadge.itermcolors")).unwrap();
+            assert_eq!(schemes[0].1, dracula);
+            assert_eq!(schemes[1].1, badge);
+        }
+
+        #[test]
+        fn all_from_iterm_rejects_single_scheme() {
+            let dracula_iterm = read_fixture("tests/fixtures/Dracula.itermcolors");
+            assert_eq!(ColorScheme::all_from_iterm(&dracula_iterm), Err(ColorError::InvalidFormat));
+        }
+
+        #[test]
+        fn to_ansi_preview() {
+            let dracula_minttyrc = read_fixture("tests/fixtures/Dracula.minttyrc");
+            let preview = ColorScheme::from_minttyrc(&dracula_minttyrc).unwrap().to_ansi_preview();
+            let lines: Vec<&str> = preview.lines().collect();
+            assert_eq!(lines.len(), 3);
+            assert_eq!(lines[0], "\x1b[48;2;248;248;242m  \x1b[0m foreground  \x1b[48;2;40;42;54m  \x1b[0m background");
+            assert!(lines[1].starts_with("\x1b[48;2;0;0;0m  \x1b[0m black  \x1b[48;2;255;85;85m  \x1b[0m red  "));
+            assert!(lines[2].ends_with("\x1b[48;2;230;230;230m  \x1b[0m bright_white"));
+            assert_eq!(preview.matches("\x1b[0m").count(), 18);
+        }
+
+        #[test]
+        fn to_yaml_with_options() {
+            let dracula_minttyrc = read_fixture("tests/fixtures/Dracula.minttyrc");
+            let scheme = ColorScheme::from_minttyrc(&dracula_minttyrc).unwrap();
+            assert_eq!(scheme.to_yaml_with(YamlOptions::default()), scheme.to_yaml());
+
+            let yaml = scheme.to_yaml_with(YamlOptions { comments: false, indent: 4 });
+            assert!(!yaml.contains('#'));
+            assert!(yaml.starts_with("colors:
+    primary:
+        background: '0x282a36'
+        foreground: '0xf8f8f2'
+
+    normal:
+        black:   '0x000000'
+"));
+
+            let flat = YamlOptions { comments: false, indent: 0 };
+            assert_eq!(scheme.to_yaml_with(flat), scheme.to_yaml_with(YamlOptions { comments: false, indent: 1 }));
+            assert!(scheme.to_yaml_with(flat).starts_with("colors:\n primary:\n  background: '0x282a36'\n"));
+
+            let mut extended = scheme.clone();
+            extended.set_indexed_color(16, Color::default()).unwrap();
+            let yaml = extended.to_yaml_extended_with(YamlOptions { comments: false, indent: 4 });
+            assert!(yaml.starts_with(&scheme.to_yaml_with(YamlOptions { comments: false, indent: 4 })));
+            assert!(yaml.ends_with("\n    indexed_colors:\n        - { index: 16, color: '0x000000' }\n"));
+        }
+
+        #[test]
+        fn from_base() {
+            let background = Color::from_hex("#282a36").unwrap();
+            let accent = Color::from_hex("#bd93f9").unwrap();
+            let scheme = ColorScheme::from_base(background, accent);
+            assert!(scheme.is_dark_theme());
+
+            let colors: Vec<(&str, Color)> = scheme.iter().map(|(name, color)| (name, *color)).collect();
+            assert_eq!(colors[1], ("background", background));
+            // The accent is closest to blue, so blue is the accent itself.
+            assert_eq!(colors[6], ("blue", accent));
+            let (red_hue, _, _) = colors[3].1.to_hsl();
+            let (accent_hue, _, _) = accent.to_hsl();
+            assert!((red_hue - (accent_hue - 240.0)).abs() < 2.0);
+            assert_eq!(colors[14].1, accent.lighten(0.2));
+            assert!(colors[0].1.contrast_ratio(&background) > 7.0);
+
+            let light = ColorScheme::from_base(Color::from_hex("#fdf6e3").unwrap(), accent);
+            assert!(!light.is_dark_theme());
+            assert!(light.iter().next().unwrap().1.is_dark());
+        }
+
+        #[cfg(feature = "yaml")]
+        #[test]
+        fn from_alacritty_full_config() {
+            let config = read_fixture("tests/fixtures/Dracula.alacritty.yml");
+            let scheme = ColorScheme::from_alacritty(&config).unwrap();
+            let dracula_minttyrc = read_fixture("tests/fixtures/Dracula.minttyrc");
+            let with_cursor = ColorScheme::from_minttyrc(&format!("{}CursorColour=248,248,242\n", dracula_minttyrc)).unwrap();
+            assert_eq!(scheme.to_minttyrc(), with_cursor.to_minttyrc());
+            assert_eq!(scheme.to_yaml(), with_cursor.to_yaml());
+            assert!(scheme.to_iterm().contains("<key>Cursor Text Color</key>"));
+            assert!(!with_cursor.to_iterm().contains("<key>Cursor Text Color</key>"));
+        }
+
+        #[cfg(feature = "yaml")]
+        #[test]
+        fn from_alacritty_nested() {
+            let config = "profiles:\n  dark:\n    font:\n      size: 12\n    colors:\n      normal:\n        red: '#ff0000'\n";
+            let scheme = ColorScheme::from_alacritty(config).unwrap();
+            assert_eq!(scheme, ColorScheme::from_minttyrc("Red=255,0,0").unwrap());
+            assert_eq!(ColorScheme::from_alacritty("window:\n  decorations: full\n"), Err(ColorError::InvalidFormat));
+        }
+
+        #[cfg(feature = "yaml")]
+        #[test]
+        fn from_alacritty_prefers_shallower_colors() {
+            let config = "a:\n  b:\n    colors:\n      normal:\n        red: '#ff0000'\nc:\n  colors:\n    normal:\n      red: '#00ff00'\n";
+            let scheme = ColorScheme::from_alacritty(config).unwrap();
+            assert_eq!(scheme, ColorScheme::from_minttyrc("Red=0,255,0").unwrap());
+        }
+
+        #[cfg(feature = "yaml")]
+        #[test]
+        fn from_alacritty_invalid_color() {
+            let config = "colors:\n  bright:\n    red: 'nope'\n";
+            let err = ColorScheme::from_alacritty(config).unwrap_err();
+            assert_eq!(err.to_string(), "invalid color for key 'bright.red': invalid format");
+            let config = "colors:\n  bright:\n    red: 0x1000000\n";
+            assert!(ColorScheme::from_alacritty(config).is_err());
+        }
+
+        #[cfg(feature = "yaml")]
+        #[test]
+        fn alacritty_round_trip() {
+            let dracula_iterm = read_fixture("tests/fixtures/Dracula.itermcolors");
+            let mut scheme = ColorScheme::from_iterm(&dracula_iterm).unwrap();
+            scheme.fill_dim_from_normal();
+            scheme.fill_256_palette();
+            let round_tripped = ColorScheme::from_alacritty(&scheme.to_yaml_extended()).unwrap();
+            assert!(round_tripped.diff(&scheme).is_empty());
+            assert_eq!(round_tripped.to_yaml_extended(), scheme.to_yaml_extended());
+        }
+
+        #[test]
+        fn convert_dir_reports_failures() {
+            let root = env::temp_dir().join(format!("colortty-convert-dir-{}", std::process::id()));
+            let (input, output) = (root.join("in"), root.join("out"));
+            fs::create_dir_all(&input).unwrap();
+            fs::create_dir_all(&output).unwrap();
+            fs::copy("tests/fixtures/Dracula.itermcolors", input.join("Dracula.itermcolors")).unwrap();
+            fs::copy("tests/fixtures/Dracula.foot.ini", input.join("Dracula.foot.ini")).unwrap();
+            fs::copy("tests/fixtures/Dracula.minttyrc", input.join("Dracula.minttyrc")).unwrap();
+            fs::write(input.join("notes.txt"), "not a color scheme\n").unwrap();
+            fs::create_dir_all(input.join("nested")).unwrap();
+
+            let report = convert_dir(&input, &output).unwrap();
+            assert_eq!(report.succeeded, 3);
+            assert_eq!(report.failed, vec![(input.join("notes.txt"), ColorError::UnknownFormat)]);
+            assert_eq!(
+                report.to_string(),
+                format!("3 converted, 1 failed\n  {}: unknown format", input.join("notes.txt").display())
+            );
+
+            let dracula_iterm = read_fixture("tests/fixtures/Dracula.itermcolors");
+            let expected = ColorScheme::from_iterm(&dracula_iterm).unwrap().to_yaml();
+            assert_eq!(read_fixture(output.join("Dracula.itermcolors.yml").to_str().unwrap()), expected);
+            let dracula_mintty = read_fixture("tests/fixtures/Dracula.minttyrc");
+            let expected = ColorScheme::from_minttyrc(&dracula_mintty).unwrap().to_yaml();
+            assert_eq!(read_fixture(output.join("Dracula.minttyrc.yml").to_str().unwrap()), expected);
+            assert!(output.join("Dracula.foot.ini.yml").is_file());
+
+            assert!(convert_dir(root.join("missing"), &output).is_err());
+            fs::remove_dir_all(&root).unwrap();
+        }
+
+        #[cfg(feature = "gzip")]
+        #[test]
+        fn convert_dir_gzip() {
+            let root = env::temp_dir().join(format!("colortty-convert-dir-gzip-{}", std::process::id()));
+            let (input, output) = (root.join("in"), root.join("out"));
+            fs::create_dir_all(&input).unwrap();
+            fs::create_dir_all(&output).unwrap();
+            fs::copy("tests/fixtures/Dracula.itermcolors.gz", input.join("Dracula.itermcolors.GZ")).unwrap();
+
+            let report = convert_dir(&input, &output).unwrap();
+            assert_eq!(report.succeeded, 1);
+            let dracula_iterm = read_fixture("tests/fixtures/Dracula.itermcolors");
+            let expected = ColorScheme::from_iterm(&dracula_iterm).unwrap().to_yaml();
+            assert_eq!(read_fixture(output.join("Dracula.itermcolors.yml").to_str().unwrap()), expected);
+            fs::remove_dir_all(&root).unwrap();
+        }
+    }
+
+    mod color_scheme_format {
+        use colortty::color::ColorSchemeFormat;
+        use super::color_scheme::read_fixture;
+
+        #[test]
+        fn from_filename() {
+            assert_eq!(ColorSchemeFormat::from_filename("Dracula.itermcolors"), Some(ColorSchemeFormat::ITerm));
+            assert_eq!(ColorSchemeFormat::from_filename("/home/me/.minttyrc"), Some(ColorSchemeFormat::Mintty));
+            assert_eq!(ColorSchemeFormat::from_filename("dracula.minttyrc"), Some(ColorSchemeFormat::Mintty));
+            assert_eq!(ColorSchemeFormat::from_filename("Dracula"), None);
+            assert_eq!(ColorSchemeFormat::from_filename("-"), None);
+        }
+
+        #[test]
+        fn from_filename_ignores_case() {
+            assert_eq!(ColorSchemeFormat::from_filename("/Themes/Dracula.ITERMCOLORS"), Some(ColorSchemeFormat::ITerm));
+            assert_eq!(ColorSchemeFormat::from_filename("Dracula.MinttyRC"), Some(ColorSchemeFormat::Mintty));
+        }
+
+        #[test]
+        fn from_filename_uses_extension_only() {
+            assert_eq!(ColorSchemeFormat::from_filename("my.itermcolors.backup"), None);
+            assert_eq!(ColorSchemeFormat::from_filename("themes.minttyrc/Dracula"), None);
+            assert_eq!(ColorSchemeFormat::from_filename("itermcolors"), None);
+        }
+
+        #[test]
+        #[cfg(feature = "gzip")]
+        fn from_filename_gzip() {
+            assert_eq!(ColorSchemeFormat::from_filename("Dracula.itermcolors.gz"), Some(ColorSchemeFormat::ITerm));
+            assert_eq!(ColorSchemeFormat::from_filename("Dracula.GZ"), None);
+            assert_eq!(ColorSchemeFormat::from_filename("Dracula.itermcolors.GZ"), Some(ColorSchemeFormat::ITerm));
+        }
+
+        #[test]
+        fn detect() {
+            let cases = [
+                ("Dracula.itermcolors", ColorSchemeFormat::ITerm),
+                ("Dracula.minttyrc", ColorSchemeFormat::Mintty),
+                ("Messy.minttyrc", ColorSchemeFormat::Mintty),
+                ("Dracula.vscode.json", ColorSchemeFormat::VSCode),
+                ("Dracula.foot.ini", ColorSchemeFormat::Foot),
+            ];
+            for &(fixture, format) in &cases {
+                let content = read_fixture(&format!("tests/fixtures/{}", fixture));
+                assert_eq!(ColorSchemeFormat::detect(&content), Some(format), "{}", fixture);
+            }
+            assert_eq!(ColorSchemeFormat::detect("{\"palette\": []}"), Some(ColorSchemeFormat::Json));
+            assert_eq!(ColorSchemeFormat::detect("{\"editor.fontSize\": 12}"), None);
+            assert_eq!(ColorSchemeFormat::detect("hello"), None);
+        }
     }
 }